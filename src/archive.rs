@@ -0,0 +1,124 @@
+//! Reading descriptors straight out of tar archives, such as CollecTor's monthly tarballs.
+//!
+//! Only available with the `archive` feature.  Archives may be uncompressed, or compressed with
+//! gzip, xz or bzip2, which is detected from their contents rather than their file name.  Each
+//! file in the archive is read into memory in turn, so the archive itself is never unpacked.
+//!
+//! ```no_run
+//! use tordesc::archive::Archive;
+//!
+//! let mut archive = Archive::open("server-descriptors-2016-08.tar.xz").unwrap();
+//! for member in archive.members().unwrap() {
+//!     let member = member.unwrap();
+//!     for result in member.parse() {
+//!         match result {
+//!             Ok(descriptor) => println!("{}: {:?}", member.path.display(), descriptor),
+//!             Err(e) => println!("{}: {}", member.path.display(), e),
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use bzip2::read::BzDecoder;
+use flate2::read::GzDecoder;
+use tar;
+use xz2::read::XzDecoder;
+
+use descriptor::{Descriptor, parse_each_any};
+use error::ParseError;
+
+/// A tar archive of descriptors.
+pub struct Archive {
+    inner: tar::Archive<Box<dyn Read>>,
+}
+
+impl Archive {
+    /// Open the archive at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Archive> {
+        Archive::new(File::open(path)?)
+    }
+
+    /// Read an archive from `reader`, decompressing it if necessary.
+    pub fn new<R: Read + 'static>(reader: R) -> io::Result<Archive> {
+        let mut reader = BufReader::new(reader);
+        let compression = compression(reader.fill_buf()?);
+        let decompressed: Box<dyn Read> = match compression {
+            Compression::None  => Box::new(reader),
+            Compression::Gzip  => Box::new(GzDecoder::new(reader)),
+            Compression::Xz    => Box::new(XzDecoder::new(reader)),
+            Compression::Bzip2 => Box::new(BzDecoder::new(reader)),
+        };
+        Ok(Archive { inner: tar::Archive::new(decompressed) })
+    }
+
+    /// Iterate over the regular files in the archive, in the order they are stored.
+    ///
+    /// An archive can only be read through once.
+    pub fn members(&mut self) -> io::Result<Members<'_>> {
+        Ok(Members { entries: self.inner.entries()? })
+    }
+}
+
+enum Compression { None, Gzip, Xz, Bzip2 }
+
+// Compressed files are recognised by the magic number they start with.
+fn compression(start: &[u8]) -> Compression {
+    if start.starts_with(b"\x1f\x8b") {
+        Compression::Gzip
+    } else if start.starts_with(b"\xfd7zXZ\x00") {
+        Compression::Xz
+    } else if start.starts_with(b"BZh") {
+        Compression::Bzip2
+    } else {
+        Compression::None
+    }
+}
+
+/// An iterator over the files in an `Archive`.
+pub struct Members<'a> {
+    entries: tar::Entries<'a, Box<dyn Read>>,
+}
+
+impl<'a> Iterator for Members<'a> {
+    type Item = io::Result<Member>;
+
+    fn next(&mut self) -> Option<io::Result<Member>> {
+        loop {
+            let mut entry = match self.entries.next()? {
+                Ok(entry) => entry,
+                Err(e)    => return Some(Err(e)),
+            };
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = match entry.path() {
+                Ok(path) => path.into_owned(),
+                Err(e)   => return Some(Err(e)),
+            };
+            let mut text = String::new();
+            return Some(entry.read_to_string(&mut text).map(|_| Member { path, text }));
+        }
+    }
+}
+
+/// A file read from an `Archive`.
+#[derive(Debug)]
+pub struct Member {
+    /// The path of the file within the archive.
+    pub path: PathBuf,
+    /// The contents of the file.
+    pub text: String,
+}
+
+impl Member {
+    /// Parse each of the descriptors in the file, based on the `@type` annotation of the first.
+    ///
+    /// See `parse_each_any`.
+    pub fn parse(&self) -> impl Iterator<Item = Result<Descriptor<'_>, ParseError>> {
+        parse_each_any(&self.text)
+    }
+}
@@ -0,0 +1,181 @@
+//! Bandwidth Files (`@type bandwidth-file 1.0`).
+//!
+//! Bandwidth files are produced by the bandwidth scanners (such as sbws), and list the measured
+//! bandwidth of each relay.  The directory authorities read them to assign the `Measured` values
+//! in their votes, from which the consensus weights are computed.
+//!
+//! Unlike most other descriptors, these are not made of keyword lines, but of `Key=Value` pairs:
+//! a header with one pair per line, and a line of space-separated pairs for each relay.
+
+use nom::{line_ending, space};
+
+use document::*;
+use grammar::*;
+use ParseError;
+use error::{finish, relocate, Syntax};
+
+/// A parsed bandwidth file.
+#[derive(Default, Debug)]
+pub struct BandwidthFile<'a> {
+    /// The `@type` annotation the file was prefixed with.  Note this is versioned independently
+    /// from the bandwidth file format itself, see `version`.
+    pub type_annotation: Option<TypeAnnotation<'a>>,
+
+    /// The time the last relay was measured, in seconds since the epoch.
+    pub timestamp: u64,
+
+    /// The version of the bandwidth file format.  This is absent in version `1.0.0`.
+    pub version: Option<&'a str>,
+
+    /// The name of the software which created the file, such as `sbws`.
+    pub software: Option<&'a str>,
+
+    /// The version of the software which created the file.
+    pub software_version: Option<&'a str>,
+
+    /// The time the file was created, in ISO 8601 format (`YYYY-MM-DDTHH:MM:SS`).
+    pub file_created: Option<&'a str>,
+
+    /// The time the first relay was measured, in ISO 8601 format.
+    pub earliest_bandwidth: Option<&'a str>,
+
+    /// The time the last relay was measured, in ISO 8601 format.
+    pub latest_bandwidth: Option<&'a str>,
+
+    /// A line for each measured relay.
+    pub relays: Vec<RelayBandwidth<'a>>,
+
+    /// Header `Key=Value` pairs we have successfully parsed, but have not been processed into
+    /// structured data, in the order they appear.
+    ///
+    /// This is the equivalent of `ServerDescriptor::unprocessed_items`.
+    pub unprocessed_keys: Vec<(&'a str, &'a str)>,
+}
+
+/// The measured bandwidth of a single relay.
+#[derive(Default, Debug)]
+pub struct RelayBandwidth<'a> {
+    /// Hex-encoded digest of the relay's identity key, prefixed with a `$`.
+    pub node_id: &'a str,
+
+    /// The relay's ed25519 identity key, encoded in base64 with trailing `=`s removed.
+    pub master_key_ed25519: Option<&'a str>,
+
+    /// Relay nickname.
+    pub nick: Option<&'a str>,
+
+    /// The measured bandwidth of the relay, in kilobytes per second, as it will appear in votes.
+    pub bw: Option<u64>,
+
+    /// `Key=Value` pairs we have successfully parsed, but have not been processed into structured
+    /// data, in the order they appear.
+    ///
+    /// This is the equivalent of `ServerDescriptor::unprocessed_items`.
+    pub unprocessed_keys: Vec<(&'a str, &'a str)>,
+}
+
+pub fn parse(input: &str) -> Result<BandwidthFile<'_>, ParseError> {
+    let (a, timestamp, header, relays) = finish(input, bandwidth_file_bucket(input.as_bytes()), Syntax::KeyValues)?;
+    Ok(transmogrify(a, timestamp, header, relays))
+}
+
+/// Parse all of the bandwidth files in `input`, skipping any which are malformed.
+pub fn parse_all(input: &str) -> Vec<BandwidthFile<'_>> {
+    parse_each(input).filter_map(Result::ok).collect()
+}
+
+/// Parse each of the bandwidth files in `input` in turn, resuming at the next one after any which
+/// are malformed.  Each of them needs to be preceded by its `@type` annotation.
+///
+/// See `server_descriptor::parse_each`.
+pub fn parse_each(input: &str) -> impl Iterator<Item = Result<BandwidthFile<'_>, ParseError>> {
+    annotated_documents(input).map(move |(start, text)| parse(text).map_err(|e| relocate(e, input, start)))
+}
+
+/// Transform the pairs returned from the parser into a BandwidthFile struct.
+fn transmogrify<'a>(annotation: TypeAnnotation<'a>,
+                    timestamp: u64,
+                    header: Vec<(&'a str, &'a str)>,
+                    relays: Vec<Vec<(&'a str, &'a str)>>) -> BandwidthFile<'a> {
+    let mut bf = BandwidthFile { type_annotation: Some(annotation), timestamp, ..Default::default() };
+
+    for (key, value) in header {
+        match key {
+            "version"            => bf.version = Some(value),
+            "software"           => bf.software = Some(value),
+            "software_version"   => bf.software_version = Some(value),
+            "file_created"       => bf.file_created = Some(value),
+            "earliest_bandwidth" => bf.earliest_bandwidth = Some(value),
+            "latest_bandwidth"   => bf.latest_bandwidth = Some(value),
+            _ => {
+                bf.unprocessed_keys.push((key, value));
+            }
+        }
+    }
+    bf.relays = relays.into_iter().map(transmogrify_relay).collect();
+    bf
+}
+
+/// Transform the pairs of a single relay line into a RelayBandwidth struct.
+fn transmogrify_relay<'a>(pairs: Vec<(&'a str, &'a str)>) -> RelayBandwidth<'a> {
+    let mut rb: RelayBandwidth = Default::default();
+
+    for (key, value) in pairs {
+        match (key, value.parse()) {
+            ("node_id", _)            => rb.node_id = value,
+            ("master_key_ed25519", _) => rb.master_key_ed25519 = Some(value),
+            ("nick", _)               => rb.nick = Some(value),
+            ("bw", Ok(bw))            => rb.bw = Some(bw),
+            _ => {
+                rb.unprocessed_keys.push((key, value));
+            }
+        }
+    }
+    rb
+}
+
+
+/// The parts of a bandwidth file, as returned from the parser.
+type Bucket<'a> = (TypeAnnotation<'a>, u64, Vec<(&'a str, &'a str)>, Vec<Vec<(&'a str, &'a str)>>);
+
+named!(bandwidth_file_bucket <Bucket<'a>>,
+    chain!(
+        annotation: map_opt!(type_annotation, accepted_as(DescriptorType::BandwidthFile)) ~
+        timestamp:  u64_digit ~ line_ending ~
+        header:     many0!(header_line) ~
+                    opt!(complete!(terminator)) ~
+        relays:     many0!(relay_line) ,
+        || { (annotation, timestamp, header, relays) }
+    )
+);
+
+// Header lines consist of a single `Key=Value` pair.  Version 1.0.0 files have no header besides
+// the timestamp.
+named!(header_line <(&'a str, &'a str)>,
+    chain!(
+        pair: key_value ~
+              line_ending ,
+        || { pair }
+    )
+);
+
+// The header is ended with a line of five `=`s, or four in version 1.1.0.
+named!(terminator,
+    recognize!(
+        chain!(
+            tag!("====") ~
+            opt!(complete!(tag!("="))) ~
+            line_ending ,
+            || {}
+        )
+    )
+);
+
+// Relay lines are made of space-separated `Key=Value` pairs, in no particular order.
+named!(relay_line < Vec<(&'a str, &'a str)> >,
+    chain!(
+        pairs: separated_nonempty_list!(space, key_value) ~
+               line_ending ,
+        || { pairs }
+    )
+);
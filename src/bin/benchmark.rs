@@ -34,7 +34,7 @@ fn find_unprocessed_items(for_filename: &str) {
 
     for sd in tordesc::server_descriptor::parse_all(&data) {
         total_count += 1;
-        if sd.unprocessed_items.len() >= 1 {
+        if !sd.unprocessed_items.is_empty() {
             unprocessed_count += 1;
             for item in sd.unprocessed_items {
                 *unprocessed_keys.entry(item.key).or_insert(0) += 1;
@@ -56,20 +56,20 @@ fn find_unprocessed_items(for_filename: &str) {
 
 fn file_data(filename: &str) -> String {
     // Open the path in read-only mode, returns `io::Result<File>`
-    let file = match File::open(&filename) {
+    let file = match File::open(filename) {
         Err(e) => panic!("{}", e),
         Ok(file) => file,
     };
     let mut data = String::new();
     let mut br = BufReader::new(file);
     br.read_to_string(&mut data).unwrap();
-    return data;
+    data
 }
 
 fn main() {
     let mut args: Vec<_> = env::args().collect();
     let filename = args.remove(1);
 
-    find_unprocessed_items(&filename); println!("");
+    find_unprocessed_items(&filename); println!();
     measure_average_advertised_bandwidth(&filename);
 }
@@ -0,0 +1,126 @@
+//! Ed25519 certificates, as specified in `cert-spec.txt`.
+//!
+//! These bind a key to an ed25519 key which signed it, for some purpose given by their type: a
+//! relay's `identity-ed25519`, for example, certifies its signing key with its master identity
+//! key.
+
+use std::convert::TryFrom;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use nom::{be_u8, be_u16, be_u32};
+use nom::IResult;
+
+/// `CERT_TYPE` of a relay's ed25519 signing key, certified by its master identity key.
+pub const SIGNING_KEY: u8 = 0x04;
+/// `CERT_TYPE` of a relay's master identity key, certified by the ed25519 key derived from its
+/// ntor onion key.
+pub const NTOR_ONION_KEY: u8 = 0x0A;
+
+/// `ExtType` of the extension holding the ed25519 key the certificate was signed with.
+pub const SIGNED_WITH_ED25519_KEY: u8 = 0x04;
+/// `ExtFlags` bit of an extension which must be understood for the certificate to be valid.
+pub const AFFECTS_VALIDATION: u8 = 0x01;
+
+/// An ed25519 certificate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ed25519Cert {
+    /// The version of the certificate format, currently always 1.
+    pub version: u8,
+    /// The purpose of the certificate, such as `SIGNING_KEY`.
+    pub cert_type: u8,
+    /// When the certificate expires, to the hour.
+    pub expiration: SystemTime,
+    /// The type of `certified_key`, which is 1 for an ed25519 key.
+    pub key_type: u8,
+    /// The key being certified.
+    pub certified_key: [u8; 32],
+    /// Any extensions, in the order they appear.
+    pub extensions: Vec<Extension>,
+    /// The ed25519 signature over all of the preceding fields.
+    pub signature: [u8; 64],
+    // the bytes of the preceding fields
+    signed: Vec<u8>,
+}
+
+/// An extension of an `Ed25519Cert`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extension {
+    /// The type of the extension, such as `SIGNED_WITH_ED25519_KEY`.
+    pub ext_type: u8,
+    /// Flags, such as `AFFECTS_VALIDATION`.
+    pub flags: u8,
+    /// The contents of the extension.
+    pub data: Vec<u8>,
+}
+
+impl Ed25519Cert {
+    /// The ed25519 key the certificate was signed with, from its `SIGNED_WITH_ED25519_KEY`
+    /// extension, if it has one.
+    pub fn signing_key(&self) -> Option<[u8; 32]> {
+        self.extensions.iter()
+            .filter(|ext| ext.ext_type == SIGNED_WITH_ED25519_KEY)
+            .filter_map(|ext| <[u8; 32]>::try_from(&ext.data[..]).ok())
+            .next()
+    }
+
+    /// The bytes covered by the `signature`.
+    pub fn signed(&self) -> &[u8] {
+        &self.signed
+    }
+}
+
+/// Parse a certificate from its encoding, such as the decoded contents of an `ED25519 CERT`
+/// object.
+///
+/// Returns `None` if it is malformed, or followed by anything else.
+pub fn parse(bytes: &[u8]) -> Option<Ed25519Cert> {
+    match ed25519_cert(bytes) {
+        IResult::Done(b"", mut cert) => {
+            cert.signed = bytes[..bytes.len() - cert.signature.len()].to_vec();
+            Some(cert)
+        }
+        _ => None,
+    }
+}
+
+//   VERSION         [1 Byte]
+//   CERT_TYPE       [1 Byte]
+//   EXPIRATION_DATE [4 Bytes]
+//   CERT_KEY_TYPE   [1 byte]
+//   CERTIFIED_KEY   [32 Bytes]
+//   N_EXTENSIONS    [1 byte]
+//   EXTENSIONS      [N_EXTENSIONS times]
+//   SIGNATURE       [64 Bytes]
+//
+// The EXPIRATION_DATE is a date in HOURS since the epoch, after which this certificate isn't
+// valid.
+named!(ed25519_cert <Ed25519Cert>,
+    chain!(
+        version:       be_u8 ~
+        cert_type:     be_u8 ~
+        expiration:    be_u32 ~
+        key_type:      be_u8 ~
+        certified_key: map_res!(take!(32), <[u8; 32]>::try_from) ~
+        n_extensions:  be_u8 ~
+        extensions:    count!(extension, n_extensions as usize) ~
+        signature:     map_res!(take!(64), <[u8; 64]>::try_from) ,
+        || { Ed25519Cert{
+            version, cert_type, key_type, certified_key, extensions, signature,
+            expiration: UNIX_EPOCH + Duration::from_secs(u64::from(expiration) * 3600),
+            signed: Vec::new(),
+        } }
+    )
+);
+
+//   ExtLength [2 bytes]
+//   ExtType   [1 byte]
+//   ExtFlags  [1 byte]
+//   ExtData   [ExtLength bytes]
+named!(extension <Extension>,
+    chain!(
+        length:   be_u16 ~
+        ext_type: be_u8 ~
+        flags:    be_u8 ~
+        data:     take!(length) ,
+        || { Extension{ ext_type, flags, data: data.to_vec() } }
+    )
+);
@@ -0,0 +1,40 @@
+//! The documents a Tor client or relay caches in its data directory.
+//!
+//! Unlike the descriptors archived by CollecTor, these have no `@type` annotation, so each file
+//! has its own entry point here.  Descriptors may instead be annotated with `@downloaded-at`,
+//! `@source` or `@last-listed`, which are kept in their `annotations`.
+//!
+//! Tor appends newly downloaded descriptors to a journal, such as `cached-descriptors.new`,
+//! before folding them into the main file.  Journals have the same format as the file they
+//! belong to, and their descriptors are more recent than those in it.
+
+use error::ParseError;
+use extra_info::{self, ExtraInfoDescriptor};
+use microdescriptor::{self, Microdescriptor};
+use network_status::{self, Consensus};
+use server_descriptor::{self, ServerDescriptor};
+
+/// Parse `cached-descriptors` or `cached-descriptors.new`.
+pub fn cached_descriptors(input: &str) -> impl Iterator<Item = Result<ServerDescriptor<'_>, ParseError>> {
+    server_descriptor::parse_each(input)
+}
+
+/// Parse `cached-extrainfo` or `cached-extrainfo.new`.
+pub fn cached_extrainfo(input: &str) -> impl Iterator<Item = Result<ExtraInfoDescriptor<'_>, ParseError>> {
+    extra_info::parse_each(input)
+}
+
+/// Parse `cached-microdescs` or `cached-microdescs.new`.
+pub fn cached_microdescs(input: &str) -> impl Iterator<Item = Result<Microdescriptor<'_>, ParseError>> {
+    microdescriptor::parse_each(input)
+}
+
+/// Parse `cached-consensus`.
+pub fn cached_consensus(input: &str) -> Result<Consensus<'_>, ParseError> {
+    network_status::parse(input)
+}
+
+/// Parse `cached-microdesc-consensus`.
+pub fn cached_microdesc_consensus(input: &str) -> Result<Consensus<'_>, ParseError> {
+    network_status::parse_microdesc(input)
+}
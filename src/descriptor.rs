@@ -0,0 +1,131 @@
+//! Parsing of any supported descriptor, based on its `@type` annotation.
+//!
+//! CollecTor prefixes every descriptor it archives with a `@type <name> <major>.<minor>` line,
+//! which tells us which parser to use.  This makes it possible to read a directory of mixed
+//! descriptors without knowing in advance what each file contains.
+
+use std::iter;
+use nom::IResult;
+
+use document::{DescriptorType, TypeAnnotation, type_annotation};
+use error::{ParseError, ErrorKind};
+use {bandwidth_file, dir_key_certificate, exit_list, extra_info, hidden_service, microdescriptor,
+     network_status, server_descriptor, torperf};
+
+/// A parsed descriptor of any of the supported types.
+#[derive(Debug)]
+pub enum Descriptor<'a> {
+    /// `@type server-descriptor` or `@type bridge-server-descriptor`.
+    ServerDescriptor(server_descriptor::ServerDescriptor<'a>),
+    /// `@type extra-info` or `@type bridge-extra-info`.
+    ExtraInfo(extra_info::ExtraInfoDescriptor<'a>),
+    /// `@type network-status-consensus-3`.
+    Consensus(network_status::Consensus<'a>),
+    /// `@type network-status-microdesc-consensus-3`.
+    MicrodescConsensus(network_status::Consensus<'a>),
+    /// `@type bridge-network-status`.
+    BridgeNetworkStatus(network_status::Consensus<'a>),
+    /// `@type network-status-vote-3`.
+    Vote(network_status::vote::Vote<'a>),
+    /// `@type microdescriptor`.
+    Microdescriptor(microdescriptor::Microdescriptor<'a>),
+    /// `@type dir-key-certificate-3`.
+    KeyCertificate(dir_key_certificate::KeyCertificate<'a>),
+    /// `@type hidden-service-descriptor`.
+    HsDescriptorV2(hidden_service::v2::HsDescriptor<'a>),
+    /// `@type hidden-service-descriptor-3`.
+    HsDescriptorV3(hidden_service::v3::HsDescriptor<'a>),
+    /// `@type tordnsel`.
+    ExitList(exit_list::ExitList<'a>),
+    /// `@type bandwidth-file`.
+    BandwidthFile(bandwidth_file::BandwidthFile<'a>),
+    /// `@type torperf`, with all the results in the file.
+    Torperf(Vec<torperf::TorperfResult<'a>>),
+}
+
+/// Parse the descriptor in `input`, with the parser chosen by its `@type` annotation.
+///
+/// Fails with `ErrorKind::UnsupportedType` if there is no parser for the type.  Input holding
+/// several descriptors, as most CollecTor files do, can be parsed with `parse_each_any`.
+pub fn parse_any(input: &str) -> Result<Descriptor<'_>, ParseError> {
+    // as for the parsers themselves, any minor version of a supported major version is accepted
+    match descriptor_type(input)? {
+        DescriptorType::ServerDescriptor =>
+            server_descriptor::parse(input).map(Descriptor::ServerDescriptor),
+        DescriptorType::ExtraInfo =>
+            extra_info::parse(input).map(Descriptor::ExtraInfo),
+        DescriptorType::Consensus =>
+            network_status::parse(input).map(Descriptor::Consensus),
+        DescriptorType::MicrodescConsensus =>
+            network_status::parse_microdesc(input).map(Descriptor::MicrodescConsensus),
+        DescriptorType::BridgeNetworkStatus =>
+            network_status::parse_bridge(input).map(Descriptor::BridgeNetworkStatus),
+        DescriptorType::Vote =>
+            network_status::vote::parse(input).map(Descriptor::Vote),
+        DescriptorType::Microdescriptor =>
+            microdescriptor::parse(input).map(Descriptor::Microdescriptor),
+        DescriptorType::KeyCertificate =>
+            dir_key_certificate::parse(input).map(Descriptor::KeyCertificate),
+        DescriptorType::HsDescriptorV2 =>
+            hidden_service::v2::parse(input).map(Descriptor::HsDescriptorV2),
+        DescriptorType::HsDescriptorV3 =>
+            hidden_service::v3::parse(input).map(Descriptor::HsDescriptorV3),
+        DescriptorType::ExitList =>
+            exit_list::parse(input).map(Descriptor::ExitList),
+        DescriptorType::BandwidthFile =>
+            bandwidth_file::parse(input).map(Descriptor::BandwidthFile),
+        DescriptorType::Torperf =>
+            torperf::parse(input).map(Descriptor::Torperf),
+    }
+}
+
+/// Parse each of the descriptors in `input`, such as a CollecTor file, with the parser chosen by
+/// the `@type` annotation of the first one.
+///
+/// The descriptors are split up as by the `parse_each` function of their type, so a malformed
+/// descriptor is returned as an error in its place without stopping the rest from being parsed.
+/// Network status documents, which come one to a file, are parsed whole.  If the first
+/// annotation is missing or unsupported, its error is the only result.
+pub fn parse_each_any(input: &str) -> Box<dyn Iterator<Item = Result<Descriptor<'_>, ParseError>> + '_> {
+    let descriptor_type = match descriptor_type(input) {
+        Ok(descriptor_type) => descriptor_type,
+        Err(e) => return Box::new(iter::once(Err(e))),
+    };
+    match descriptor_type {
+        DescriptorType::ServerDescriptor =>
+            Box::new(server_descriptor::parse_each(input).map(|r| r.map(Descriptor::ServerDescriptor))),
+        DescriptorType::ExtraInfo =>
+            Box::new(extra_info::parse_each(input).map(|r| r.map(Descriptor::ExtraInfo))),
+        DescriptorType::Microdescriptor =>
+            Box::new(microdescriptor::parse_each(input).map(|r| r.map(Descriptor::Microdescriptor))),
+        DescriptorType::KeyCertificate =>
+            Box::new(dir_key_certificate::parse_each(input).map(|r| r.map(Descriptor::KeyCertificate))),
+        DescriptorType::HsDescriptorV2 =>
+            Box::new(hidden_service::v2::parse_each(input).map(|r| r.map(Descriptor::HsDescriptorV2))),
+        DescriptorType::HsDescriptorV3 =>
+            Box::new(hidden_service::v3::parse_each(input).map(|r| r.map(Descriptor::HsDescriptorV3))),
+        DescriptorType::ExitList =>
+            Box::new(exit_list::parse_each(input).map(|r| r.map(Descriptor::ExitList))),
+        DescriptorType::BandwidthFile =>
+            Box::new(bandwidth_file::parse_each(input).map(|r| r.map(Descriptor::BandwidthFile))),
+        DescriptorType::Torperf =>
+            Box::new(torperf::parse_each(input).map(|r| r.map(Descriptor::Torperf))),
+
+        DescriptorType::Consensus | DescriptorType::MicrodescConsensus |
+        DescriptorType::BridgeNetworkStatus | DescriptorType::Vote =>
+            Box::new(iter::once(parse_any(input))),
+    }
+}
+
+// The type of the descriptor in `input`, from its `@type` annotation.
+fn descriptor_type(input: &str) -> Result<DescriptorType, ParseError> {
+    let annotation = match type_annotation(input.as_bytes()) {
+        IResult::Done(_i, annotation) => annotation,
+        _ => return Err(ParseError::new(ErrorKind::BadAnnotation, input, 0, None)),
+    };
+    annotation.descriptor_type().ok_or_else(|| {
+        let TypeAnnotation { name, major, minor } = annotation;
+        let kind = ErrorKind::UnsupportedType { name: name.to_string(), major, minor };
+        ParseError::new(kind, input, 0, Some("@type"))
+    })
+}
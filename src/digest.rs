@@ -0,0 +1,96 @@
+//! Digests of descriptors, by which they are referred to from other documents.
+//!
+//! A server descriptor is referred to by the SHA1 digest of its signed portion from the `r` line
+//! of a consensus, for example, and an extra-info descriptor by both its SHA1 and SHA256
+//! digests from the `extra-info-digest` line of its relay's server descriptor.
+
+use std::str;
+use sha1::Sha1;
+use sha2::{Sha256, Digest as _};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use document::decode_base64;
+
+/// The digest of (part of) a document.
+///
+/// Relays are also identified by a digest, of their identity key, so a `fingerprint` is parsed
+/// into one too.  This lets the documents that list relays be joined on it, whichever way each of
+/// them writes it.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Digest(Vec<u8>);
+
+impl Digest {
+    /// The SHA1 digest of `data`.
+    pub fn sha1(data: &[u8]) -> Digest {
+        Digest(Sha1::digest(data).to_vec())
+    }
+
+    /// The SHA256 digest of `data`.
+    pub fn sha256(data: &[u8]) -> Digest {
+        Digest(Sha256::digest(data).to_vec())
+    }
+
+    /// Decode a digest encoded in hex, with or without spaces between groups of characters, or
+    /// `None` if it isn't valid hex.
+    pub fn from_hex(text: &str) -> Option<Digest> {
+        let hex: Vec<u8> = text.bytes().filter(|&c| c != b' ').collect();
+        if hex.is_empty() || !hex.len().is_multiple_of(2) || !hex.iter().all(u8::is_ascii_hexdigit) {
+            return None;
+        }
+        hex.chunks(2)
+            .map(|pair| u8::from_str_radix(str::from_utf8(pair).ok()?, 16).ok())
+            .collect::<Option<Vec<u8>>>()
+            .map(Digest)
+    }
+
+    /// Decode a digest encoded in base64, with or without trailing `=`s, or `None` if it isn't
+    /// valid base64.
+    pub fn from_base64(text: &str) -> Option<Digest> {
+        decode_base64(text).map(Digest)
+    }
+
+    /// Decode a relay's fingerprint, the hex of the SHA1 digest of its identity key, or `None` if
+    /// it isn't the hex of exactly 20 bytes.
+    pub fn from_fingerprint(text: &str) -> Option<Digest> {
+        Digest::from_hex(text).filter(|digest| digest.0.len() == 20)
+    }
+
+    /// The bytes of the digest.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The digest encoded in upper-case hex, as in `extra-info-digest`.
+    pub fn hex(&self) -> String {
+        self.0.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    /// The digest encoded in upper-case hex, with a space after every 4 characters, as in a
+    /// `fingerprint`.
+    pub fn spaced_hex(&self) -> String {
+        self.0.chunks(2).map(|pair| pair.iter().map(|b| format!("{:02X}", b)).collect::<String>())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The digest encoded in base64 with trailing `=`s removed, as in router status entries.
+    pub fn base64(&self) -> String {
+        STANDARD_NO_PAD.encode(&self.0)
+    }
+}
+
+/// The portion of a descriptor's `text` covered by its `router-signature`, from the start up to
+/// and including the `router-signature` line, or `None` if it has no signature.
+pub fn signed_portion(text: &str) -> Option<&str> {
+    up_to(text, "\nrouter-signature\n")
+}
+
+/// The portion of a descriptor's `text` covered by its `router-sig-ed25519`, from the start up to
+/// and including the space after `router-sig-ed25519`, or `None` if it has no such signature.
+pub fn ed25519_signed_portion(text: &str) -> Option<&str> {
+    up_to(text, "\nrouter-sig-ed25519 ")
+}
+
+fn up_to<'a>(text: &'a str, end: &str) -> Option<&'a str> {
+    text.find(end).map(|pos| &text[..pos + end.len()])
+}
@@ -0,0 +1,119 @@
+//! Directory Authority Key Certificates (`@type dir-key-certificate-3 1.0`).
+//!
+//! Each directory authority has a long-term offline identity key, which it uses to certify a
+//! medium-term signing key.  The signing key is what the authority actually signs its votes and
+//! consensus signatures with, so these certificates are needed to validate those signatures.
+//!
+//! Key certificates are also embedded in each network status vote.
+
+use std::str;
+use nom::IResult;
+
+use document::*;
+use grammar::*;
+use ParseError;
+use error::{finish, relocate, Syntax};
+
+/// Common data from a parsed authority key certificate.
+#[derive(Default, Debug)]
+pub struct KeyCertificate<'a> {
+    /// The `@type` annotation the certificate was prefixed with, which records the version of the
+    /// format.  Certificates embedded in a vote have none.
+    pub type_annotation: Option<TypeAnnotation<'a>>,
+
+    /// The version of the key certificate format, currently `3`.
+    pub version: Option<u64>,
+
+    /// An IP:Port for the authority's directory port.
+    pub address: Option<&'a str>,
+
+    /// Hex-encoded digest of the authority's identity key, with no spaces.
+    pub fingerprint: Option<&'a str>,
+
+    /// The authority's long-term RSA identity key.  It MUST be at least 2048 bits.
+    pub identity_key: Option<Object<'a>>,
+
+    /// The time, in UTC, when this certificate was generated, as `YYYY-MM-DD HH:MM:SS`.
+    pub published: Option<&'a str>,
+
+    /// The time, in UTC, after which this certificate is no longer valid, as
+    /// `YYYY-MM-DD HH:MM:SS`.
+    pub expires: Option<&'a str>,
+
+    /// The authority's medium-term RSA signing key.
+    pub signing_key: Option<Object<'a>>,
+
+    /// A signature of the digest of the identity key, made with the signing key.
+    ///
+    /// The object type keyword is `ID SIGNATURE` for newer certificates and `SIGNATURE` for
+    /// older ones.
+    pub crosscert: Option<Object<'a>>,
+
+    /// A signature of the whole certificate, made with the identity key.
+    pub certification: Option<Object<'a>>,
+
+    /// Items we have successfully parsed from a KeyCertificate, but have not been processed into
+    /// structured data.
+    ///
+    /// See `ServerDescriptor::unprocessed_items` for details.
+    pub unprocessed_items: Vec<Item<'a>>,
+}
+
+pub fn parse(input: &str) -> Result<KeyCertificate<'_>, ParseError> {
+    let (annotation, kc) = finish(input, key_certificate_bucket(input.as_bytes()), Syntax::KeywordLines)?;
+    Ok(annotated(annotation, kc))
+}
+
+/// Parse all of the key certificates in `input`, skipping any which are malformed.
+pub fn parse_all(input: &str) -> Vec<KeyCertificate<'_>> {
+    parse_each(input).filter_map(Result::ok).collect()
+}
+
+/// Parse each of the key certificates in `input` in turn, resuming at the next one after any
+/// which are malformed.
+///
+/// See `server_descriptor::parse_each`.
+pub fn parse_each(input: &str) -> impl Iterator<Item = Result<KeyCertificate<'_>, ParseError>> {
+    documents(input, "dir-key-certificate-version")
+        .map(move |(start, text)| parse(text).map_err(|e| relocate(e, input, start)))
+}
+
+/// Transform a "bucket of items" following a `@type` annotation into a KeyCertificate struct.
+fn annotated<'a>(annotation: TypeAnnotation<'a>, item_bucket: Vec<Item<'a>>) -> KeyCertificate<'a> {
+    KeyCertificate { type_annotation: Some(annotation), ..transmogrify(item_bucket) }
+}
+
+/// Transform a "bucket of items" into a KeyCertificate struct.
+#[doc(hidden)]
+pub fn transmogrify<'a>(item_bucket: Vec<Item<'a>>) -> KeyCertificate<'a> {
+    let mut kc: KeyCertificate = Default::default();
+
+    for item in item_bucket {
+        match item.key {
+            "dir-address"           => singleton_arg!(item => kc.address),
+            "fingerprint"           => singleton_arg!(item => kc.fingerprint),
+            "dir-key-published"     => singleton_arg!(item => kc.published),
+            "dir-key-expires"       => singleton_arg!(item => kc.expires),
+            "dir-identity-key"      => typed_obj!(item => kc.identity_key),
+            "dir-signing-key"       => typed_obj!(item => kc.signing_key),
+            "dir-key-crosscert"     => typed_obj!(item => kc.crosscert),
+            "dir-key-certification" => typed_obj!(item => kc.certification),
+
+            "dir-key-certificate-version" => use_parser!(item => kc, u64_digit, |r| kc.version = Some(r)),
+
+            _ => {
+                kc.unprocessed_items.push(item);
+            }
+        }
+    }
+    kc
+}
+
+
+named!(key_certificate_bucket <(TypeAnnotation<'a>, Vec<Item<'a>>)>,
+    chain!(
+        annotation: map_opt!(type_annotation, accepted_as(DescriptorType::KeyCertificate)) ~
+        items:      many1!(item) ,
+        || { (annotation, items) }
+    )
+);
@@ -53,8 +53,14 @@
 //  with the characters "x-" or "X-", to guarantee that they will not conflict
 //  with keywords used by future versions of Tor.
 
+use std::iter;
 use std::str;
 use nom::{line_ending, not_line_ending, space, alphanumeric};
+use nom::IResult;
+use grammar::u64_digit;
+use base64::Engine;
+use base64::alphabet;
+use base64::engine::{GeneralPurpose, GeneralPurposeConfig, DecodePaddingMode};
 
 /// A Document consists of one or more Items.
 #[derive(Debug)]
@@ -66,23 +72,39 @@ pub struct Item<'a> {
     /// Each object is a block of encoded data in pseudo-Open-PGP-style armor. (cf. RFC 2440)
     pub objs: Vec<&'a str>,
 }
-named!(pub item <Item>,
+named!(pub item <Item<'_>>,
     chain!(
         kl:   keyword_line ~
         objs: many0!(map_res!(object, str::from_utf8)) ,
-        || { Item{ key: kl.key,  args: kl.args, objs: objs } }
+        || { Item{ key: kl.key,  args: kl.args, objs } }
     )
 );
 
 
+/// Parses one or more Items, also returning the raw text which they were parsed from.
+///
+/// Documents are referred to elsewhere by a digest of their text, so some documents need to keep
+/// it around after parsing.
+pub fn items_with_text(input: &[u8]) -> IResult<&[u8], (&str, Vec<Item<'_>>)> {
+    match many1!(input, item) {
+        IResult::Done(rest, items) => {
+            // safe, since items are only made up of valid UTF-8 sequences and line endings
+            let text = str::from_utf8(&input[..input.len() - rest.len()]).unwrap();
+            IResult::Done(rest, (text, items))
+        }
+        IResult::Error(e)      => IResult::Error(e),
+        IResult::Incomplete(n) => IResult::Incomplete(n),
+    }
+}
+
 #[derive(Debug)]
 struct KeywordLine<'a> { key: &'a str, args: Option<&'a str> }
-named!(keyword_line <KeywordLine>,
+named!(keyword_line <KeywordLine<'a>>,
     chain!(
         key:  map_res!(keyword, str::from_utf8) ~
         args: opt!( map_res!(keyword_args, str::from_utf8) ) ~
         line_ending ,
-        || { KeywordLine{ key: key, args: args } }
+        || { KeywordLine{ key, args } }
     )
 );
 
@@ -93,12 +115,66 @@ named!(keyword_args,
         || { args }
     )
 );
-named!(keyword, recognize!(many1!(keyword_char)));
+// a line starting with dashes is the BeginLine or EndLine of an object, not a keyword line.
+named!(keyword,
+    recognize!(
+        chain!(
+            not!(tag!("-----")) ~
+            many1!(keyword_char) ,
+            || {}
+        )
+    )
+);
 named!(keyword_char,
     alt!(alphanumeric | tag!("-"))
 );
 
 
+/// An Object, along with the keyword from its BeginLine and EndLine.
+#[derive(Debug, PartialEq)]
+pub struct Object<'a> {
+    /// The object type keyword, such as `RSA PUBLIC KEY` or `SIGNATURE`.
+    pub keyword: &'a str,
+    /// The full text of the object, including the BeginLine and EndLine.
+    pub text: &'a str,
+}
+impl<'a> Object<'a> {
+    /// Decode the base64-encoded data contained in the object.
+    ///
+    /// Returns `None` if the data is not valid base64.
+    pub fn decode(&self) -> Option<Vec<u8>> {
+        let data: String = self.text.lines()
+            .filter(|line| !line.starts_with("-----"))
+            .collect();
+        BASE64_LENIENT.decode(data).ok()
+    }
+}
+
+/// Decode a base64-encoded argument, such as a key or signature, with or without trailing `=`s.
+pub fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    BASE64_LENIENT.decode(text).ok()
+}
+
+// Objects are usually padded with `=`s, but we don't need to insist on it.
+const BASE64_LENIENT: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+named!(pub typed_object <Object<'_>>,
+    chain!(
+        keyword: peek!(
+                     chain!(
+                         tag!("-----BEGIN ") ~
+                         k: map_res!(object_type, str::from_utf8) ,
+                         || { k }
+                     )
+                 ) ~
+        text:    map_res!(object, str::from_utf8) ,
+        || { Object{ keyword, text } }
+    )
+);
+
 named!(object,
     recognize!(
         chain!(
@@ -129,6 +205,200 @@ named!(object_char,
 );
 
 
+
+/// The `@type <name> <major>.<minor>` annotation, which CollecTor prefixes each descriptor with.
+///
+/// CollecTor only increments the minor version for backwards-compatible changes to a format, so
+/// a parser for one major version can read any of its minor versions.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct TypeAnnotation<'a> {
+    /// The type of the descriptor, such as `server-descriptor`.
+    pub name: &'a str,
+    /// The major version of the descriptor format.
+    pub major: u64,
+    /// The minor version of the descriptor format.
+    pub minor: u64,
+}
+named!(pub type_annotation <TypeAnnotation<'_>>,
+    chain!(
+               tag!("@type") ~
+               space ~
+        name:  map_res!(is_not!(" \r\n"), str::from_utf8) ~
+               space ~
+        major: u64_digit ~
+               tag!(".") ~
+        minor: u64_digit ~
+               line_ending ,
+        || { TypeAnnotation{ name, major, minor } }
+    )
+);
+
+/// A kind of descriptor there is a parser for, which its `@type` annotation tells apart.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DescriptorType {
+    /// `@type server-descriptor` or `@type bridge-server-descriptor`.
+    ServerDescriptor,
+    /// `@type extra-info` or `@type bridge-extra-info`.
+    ExtraInfo,
+    /// `@type network-status-consensus-3`.
+    Consensus,
+    /// `@type network-status-microdesc-consensus-3`.
+    MicrodescConsensus,
+    /// `@type bridge-network-status`.
+    BridgeNetworkStatus,
+    /// `@type network-status-vote-3`.
+    Vote,
+    /// `@type microdescriptor`.
+    Microdescriptor,
+    /// `@type dir-key-certificate-3`.
+    KeyCertificate,
+    /// `@type hidden-service-descriptor`.
+    HsDescriptorV2,
+    /// `@type hidden-service-descriptor-3`.
+    HsDescriptorV3,
+    /// `@type tordnsel`.
+    ExitList,
+    /// `@type bandwidth-file`.
+    BandwidthFile,
+    /// `@type torperf`.
+    Torperf,
+}
+
+// The name and major version of each `@type` we can parse, and the kind of descriptor it is.
+const SUPPORTED_TYPES: &[(&str, u64, DescriptorType)] = &[
+    ("server-descriptor",                    1, DescriptorType::ServerDescriptor),
+    ("bridge-server-descriptor",             1, DescriptorType::ServerDescriptor),
+    ("extra-info",                           1, DescriptorType::ExtraInfo),
+    ("bridge-extra-info",                    1, DescriptorType::ExtraInfo),
+    ("network-status-consensus-3",           1, DescriptorType::Consensus),
+    ("network-status-microdesc-consensus-3", 1, DescriptorType::MicrodescConsensus),
+    ("bridge-network-status",                1, DescriptorType::BridgeNetworkStatus),
+    ("network-status-vote-3",                1, DescriptorType::Vote),
+    ("microdescriptor",                      1, DescriptorType::Microdescriptor),
+    ("dir-key-certificate-3",                1, DescriptorType::KeyCertificate),
+    ("hidden-service-descriptor",            1, DescriptorType::HsDescriptorV2),
+    ("hidden-service-descriptor-3",          1, DescriptorType::HsDescriptorV3),
+    ("tordnsel",                             1, DescriptorType::ExitList),
+    ("bandwidth-file",                       1, DescriptorType::BandwidthFile),
+    ("torperf",                              1, DescriptorType::Torperf),
+];
+
+impl<'a> TypeAnnotation<'a> {
+    /// The kind of descriptor the annotation is for, or `None` if there is no parser for its
+    /// type, or for its major version of the type.  Any minor version is accepted.
+    pub fn descriptor_type(&self) -> Option<DescriptorType> {
+        SUPPORTED_TYPES.iter()
+            .find(|&&(name, major, _)| name == self.name && major == self.major)
+            .map(|&(_, _, descriptor_type)| descriptor_type)
+    }
+}
+
+/// A filter passing only annotations for `descriptor_type`, with which each parser accepts the
+/// annotations of its own type, as in `map_opt!(type_annotation, accepted_as(..))`.
+pub fn accepted_as<'a>(descriptor_type: DescriptorType) -> impl Fn(TypeAnnotation<'a>) -> Option<TypeAnnotation<'a>> {
+    move |annotation| match annotation.descriptor_type() {
+        Some(t) if t == descriptor_type => Some(annotation),
+        _ => None,
+    }
+}
+
+/// Any other annotation preceding a descriptor, such as the `@downloaded-at` and `@source` lines
+/// Tor prefixes the descriptors in its cached descriptor files with.
+#[derive(Debug, PartialEq)]
+pub struct Annotation<'a> {
+    /// The keyword of the annotation, without the leading `@`.
+    pub key: &'a str,
+    /// All arguments, if any, following the keyword.
+    pub args: Option<&'a str>,
+}
+named!(pub annotation <Annotation<'_>>,
+    map_opt!(
+        chain!(
+                tag!("@") ~
+            kl: keyword_line ,
+            || { kl }
+        ),
+        not_type_annotation
+    )
+);
+// `@type` annotations are parsed by `type_annotation`, so must not be mistaken for other ones.
+fn not_type_annotation(kl: KeywordLine) -> Option<Annotation> {
+    match kl.key {
+        "type" => None,
+        _      => Some(Annotation{ key: kl.key, args: kl.args }),
+    }
+}
+/// Split `input` into the text of each of the documents it contains, along with their offsets.
+///
+/// A new document starts at any annotation following the body of the previous one, or at an item
+/// with `keyword`, such as `router` for server descriptors.  This doesn't depend on the documents
+/// being well-formed, so parsing can resume at the next document after a malformed one.  Blank
+/// lines between documents belong to neither of them.
+pub fn documents<'a>(input: &'a str, keyword: &'a str) -> impl Iterator<Item = (usize, &'a str)> + 'a {
+    split_documents(input, Some(keyword))
+}
+
+/// Split `input` into documents which each start with an annotation, such as the files of a
+/// format with no keyword lines to tell where a document starts.  See `documents`.
+pub fn annotated_documents(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    split_documents(input, None)
+}
+
+fn split_documents<'a>(input: &'a str, keyword: Option<&'a str>) -> impl Iterator<Item = (usize, &'a str)> + 'a {
+    let mut start = 0;
+    iter::from_fn(move || {
+        start += blank_lines(&input[start..]);
+        if start == input.len() {
+            return None;
+        }
+        let rest = &input[start..];
+        let len = find_next_document(rest, keyword).unwrap_or(rest.len());
+        let text = rest[..len].trim_end_matches(['\r', '\n']);
+        start += len;
+        // keep the line ending of the last line, which is part of its item
+        Some((start - len, &rest[..text.len() + line_ending_len(&rest[text.len()..])]))
+    })
+}
+
+/// The offset of the second document in `input`, if there is one.  See `documents`.
+pub fn next_document(input: &str, keyword: &str) -> Option<usize> {
+    find_next_document(input, Some(keyword))
+}
+
+fn find_next_document(input: &str, keyword: Option<&str>) -> Option<usize> {
+    let mut offset = 0;
+    let mut in_body = false;
+    for line in input.split_inclusive('\n') {
+        if in_body && (line.starts_with('@') || keyword.is_some_and(|k| starts_document(line, k))) {
+            return Some(offset);
+        }
+        in_body |= !line.starts_with('@') && !is_blank(line);
+        offset += line.len();
+    }
+    None
+}
+
+/// Whether `line` could be the first line of a document: an annotation, or an item with `keyword`.
+pub fn starts_document(line: &str, keyword: &str) -> bool {
+    line.starts_with('@') || match line.strip_prefix(keyword) {
+        Some(rest) => rest.is_empty() || rest.starts_with([' ', '\t', '\r', '\n']),
+        None       => false,
+    }
+}
+
+/// The length of the blank lines at the start of `text`.
+pub fn blank_lines(text: &str) -> usize {
+    text.split_inclusive('\n').take_while(|line| is_blank(line)).map(str::len).sum()
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim_end_matches(['\r', '\n']).is_empty()
+}
+
+fn line_ending_len(text: &str) -> usize {
+    if text.starts_with("\r\n") { 2 } else if text.starts_with('\n') { 1 } else { 0 }
+}
+
 // #[cfg(test)]
 // mod tests {
 //     use super::*;
@@ -0,0 +1,194 @@
+//! Errors returned when a document can't be parsed.
+//!
+//! Nom only tells us where parsing stopped (if even that), so the reason for a failure is worked
+//! out afterwards from the text at that position.
+
+use std::error;
+use std::fmt;
+use nom::{Err, IResult};
+use document::blank_lines;
+
+/// The reason a document could not be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    /// The `@type` annotation, or another annotation, is missing or malformed.
+    BadAnnotation,
+    /// The `@type` annotation names a type, or a major version of it, that can't be parsed.
+    UnsupportedType { name: String, major: u64, minor: u64 },
+    /// The document does not start with the keyword its type requires, such as `router`.
+    UnexpectedKeyword,
+    /// A line could not be parsed as a keyword line.
+    MalformedKeywordLine,
+    /// A line could not be parsed as a list of `Key=Value` pairs.
+    MalformedKeyValue,
+    /// An object's BeginLine is not followed by base64-encoded data and a matching EndLine.
+    UnterminatedObject,
+    /// The input ended in the middle of the document.
+    UnexpectedEnd,
+}
+
+/// An error encountered while parsing a document, and where in the input it occurred.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// What went wrong.
+    pub kind: ErrorKind,
+    /// The byte offset into the input at which parsing failed.
+    pub offset: usize,
+    /// The line at which parsing failed, starting from 1.
+    pub line: usize,
+    /// The column at which parsing failed, in characters starting from 1.
+    pub column: usize,
+    /// The keyword of the item being parsed when it failed, if any.
+    pub keyword: Option<String>,
+}
+
+impl ParseError {
+    /// Make an error of `kind` at byte `offset` into `input`.
+    pub fn new(kind: ErrorKind, input: &str, offset: usize, keyword: Option<&str>) -> ParseError {
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |nl| nl + 1);
+        ParseError {
+            kind,
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            keyword: keyword.map(String::from),
+        }
+    }
+}
+
+/// Move an error found in `input[start..]` to its position in the whole of `input`.
+pub fn relocate(e: ParseError, input: &str, start: usize) -> ParseError {
+    ParseError::new(e.kind, input, start + e.offset, e.keyword.as_deref())
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorKind::BadAnnotation        => write!(f, "missing or malformed annotation"),
+            ErrorKind::UnexpectedKeyword    => write!(f, "unexpected keyword"),
+            ErrorKind::MalformedKeywordLine => write!(f, "malformed keyword line"),
+            ErrorKind::MalformedKeyValue    => write!(f, "malformed Key=Value pair"),
+            ErrorKind::UnterminatedObject   => write!(f, "unterminated object"),
+            ErrorKind::UnexpectedEnd        => write!(f, "unexpected end of input"),
+            ErrorKind::UnsupportedType { ref name, major, minor } => {
+                write!(f, "unsupported descriptor type @type {} {}.{}", name, major, minor)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.kind, self.line, self.column)?;
+        if let Some(ref keyword) = self.keyword {
+            write!(f, " in {:?}", keyword)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ParseError {}
+
+
+/// The syntax of the lines making up the body of a document, to tell what was malformed.
+#[derive(Clone, Copy)]
+pub enum Syntax {
+    /// Keyword lines, optionally followed by objects, as in most of the directory protocol.
+    KeywordLines,
+    /// Space-separated `Key=Value` pairs, as in bandwidth files and torperf results.
+    KeyValues,
+}
+
+/// Turn the result of parsing a single document from `input` into a `Result`.
+///
+/// Parsing must have reached the end of the input, apart from any blank lines, otherwise the line
+/// where it stopped is reported as the error.  Input holding several documents has to be split
+/// up first, see `document::documents`.
+pub fn finish<'a, O>(input: &'a str, result: IResult<&'a [u8], O>, syntax: Syntax) -> Result<O, ParseError> {
+    match result {
+        IResult::Done(rest, o) => {
+            let offset = input.len() - rest.len();
+            let leftover = offset + blank_lines(&input[offset..]);
+            if leftover == input.len() {
+                Ok(o)
+            } else {
+                Err(diagnose(input, leftover, syntax))
+            }
+        }
+        IResult::Error(e)      => Err(diagnose(input, error_offset(input, &e), syntax)),
+        IResult::Incomplete(_) => Err(incomplete(input)),
+    }
+}
+
+// The offset of the innermost position recorded in a Nom error, or the start of the input if
+// there is none.
+fn error_offset(input: &str, e: &Err<&[u8]>) -> usize {
+    match *e {
+        Err::Code(_)                        => 0,
+        Err::Node(_, ref next)              => error_offset(input, next),
+        Err::Position(_, p)                 => input.len() - p.len(),
+        Err::NodePosition(_, p, ref next)   => {
+            match error_offset(input, next) {
+                0 => input.len() - p.len(),
+                offset => offset,
+            }
+        }
+    }
+}
+
+// Work out why parsing failed at `offset`, from the line found there, or the next line that isn't
+// blank.
+fn diagnose(input: &str, offset: usize, syntax: Syntax) -> ParseError {
+    let offset = offset + blank_lines(&input[offset..]);
+    let rest = &input[offset..];
+    let line = rest.lines().next().unwrap_or("");
+    let first_word = line.split(' ').next().filter(|w| !w.is_empty());
+
+    let body_start = input.split_inclusive('\n')
+        .take_while(|l| l.starts_with('@') || blank_lines(l) == l.len())
+        .map(str::len)
+        .sum::<usize>();
+
+    if rest.is_empty() {
+        incomplete(input)
+    } else if line.starts_with('@') {
+        ParseError::new(ErrorKind::BadAnnotation, input, offset, first_word)
+    } else if line.starts_with("-----BEGIN ") {
+        ParseError::new(ErrorKind::UnterminatedObject, input, offset, previous_keyword(input, offset))
+    } else if offset == 0 {
+        ParseError::new(ErrorKind::BadAnnotation, input, offset, None)
+    } else {
+        let kind = match syntax {
+            Syntax::KeywordLines if offset == body_start => ErrorKind::UnexpectedKeyword,
+            Syntax::KeywordLines => ErrorKind::MalformedKeywordLine,
+            Syntax::KeyValues    => ErrorKind::MalformedKeyValue,
+        };
+        ParseError::new(kind, input, offset, first_word)
+    }
+}
+
+// The input ended before the document did.  The most likely cause is an object missing its
+// EndLine, otherwise it's reported at the end of the input.
+fn incomplete(input: &str) -> ParseError {
+    let begin = input.rfind("\n-----BEGIN ").map(|pos| pos + 1);
+    let end = input.rfind("\n-----END ").map(|pos| pos + 1);
+    match begin {
+        Some(begin) if end.is_none_or(|end| end < begin) => {
+            ParseError::new(ErrorKind::UnterminatedObject, input, begin, previous_keyword(input, begin))
+        }
+        _ => {
+            let last_line = input.trim_end_matches('\n').rfind('\n').map_or(0, |nl| nl + 1);
+            let keyword = input[last_line..].split(' ').next().filter(|w| !w.is_empty());
+            ParseError::new(ErrorKind::UnexpectedEnd, input, input.len(), keyword)
+        }
+    }
+}
+
+// The keyword of the last line that isn't blank before `offset`, which an object found there
+// belongs to.
+fn previous_keyword(input: &str, offset: usize) -> Option<&str> {
+    input[..offset].lines().rfind(|l| !l.trim_end().is_empty())
+        .and_then(|l| l.split(' ').next())
+        .filter(|w| !w.is_empty())
+}
@@ -0,0 +1,168 @@
+//! TorDNSEL Exit Lists (`@type tordnsel 1.0`).
+//!
+//! The exit list service builds circuits through every exit relay and records the addresses
+//! that connections were actually observed to come from, which may differ from the addresses
+//! the relays advertise in their server descriptors.
+
+use std::str;
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use nom::space;
+use nom::IResult;
+
+use document::*;
+use digest::Digest;
+use grammar::*;
+use ParseError;
+use error::{finish, relocate, Syntax};
+
+/// A parsed exit list.
+#[derive(Default, Debug)]
+pub struct ExitList<'a> {
+    /// The `@type` annotation the descriptor was prefixed with, which records the version of the
+    /// format.
+    pub type_annotation: Option<TypeAnnotation<'a>>,
+
+    /// The time, in UTC, when the exit list was downloaded, as `YYYY-MM-DD HH:MM:SS`.
+    pub downloaded: Option<&'a str>,
+
+    /// An entry for each exit relay that was scanned.
+    pub exit_nodes: Vec<ExitNode<'a>>,
+
+    /// Items we have successfully parsed from an ExitList, but have not been processed into
+    /// structured data.
+    ///
+    /// See `ServerDescriptor::unprocessed_items` for details.
+    pub unprocessed_items: Vec<Item<'a>>,
+}
+
+/// The observed exit addresses of a single relay.
+#[derive(Default, Debug)]
+pub struct ExitNode<'a> {
+    /// The digest of the relay's identity key, which is also its `ServerDescriptor::fingerprint`.
+    ///
+    /// Exit lists write it in hex with no spaces.  `None` if it isn't a valid fingerprint, in
+    /// which case the `ExitNode` item is left in `unprocessed_items`.
+    pub fingerprint: Option<Digest>,
+
+    /// The publication time of the relay's most recent descriptor, as `YYYY-MM-DD HH:MM:SS`.
+    pub published: Option<&'a str>,
+
+    /// The time, in UTC, of the last network status in which the relay was listed as running.
+    pub last_status: Option<&'a str>,
+
+    /// The addresses connections through the relay were observed to originate from.
+    pub exit_addresses: Vec<ExitAddress<'a>>,
+
+    /// Items we have successfully parsed from an ExitNode, but have not been processed into
+    /// structured data.
+    ///
+    /// See `ServerDescriptor::unprocessed_items` for details.
+    pub unprocessed_items: Vec<Item<'a>>,
+}
+
+/// An address a relay was observed to exit from.
+#[derive(Debug, PartialEq)]
+pub struct ExitAddress<'a> {
+    /// IPv4 address of the exit connection.
+    pub address: Ipv4Addr,
+    /// The time, in UTC, of the scan which observed it, as `YYYY-MM-DD HH:MM:SS`.
+    pub observed: &'a str,
+}
+
+impl<'a> ExitList<'a> {
+    /// Index the entries of the exit list by their `fingerprint`, so they can be looked up by the
+    /// `fingerprint` of a server descriptor.  Entries without a valid `fingerprint` are left out.
+    pub fn index_by_fingerprint(self) -> HashMap<Digest, ExitNode<'a>> {
+        self.exit_nodes.into_iter().filter_map(|en| Some((en.fingerprint.clone()?, en))).collect()
+    }
+}
+
+pub fn parse(input: &str) -> Result<ExitList<'_>, ParseError> {
+    let (annotation, items) = finish(input, exit_list_bucket(input.as_bytes()), Syntax::KeywordLines)?;
+    Ok(transmogrify(annotation, items))
+}
+
+/// Parse all of the exit lists in `input`, skipping any which are malformed.
+pub fn parse_all(input: &str) -> Vec<ExitList<'_>> {
+    parse_each(input).filter_map(Result::ok).collect()
+}
+
+/// Parse each of the exit lists in `input` in turn, resuming at the next one after any which are
+/// malformed.  Each of them needs to be preceded by its `@type` annotation.
+///
+/// See `server_descriptor::parse_each`.
+pub fn parse_each(input: &str) -> impl Iterator<Item = Result<ExitList<'_>, ParseError>> {
+    annotated_documents(input).map(move |(start, text)| parse(text).map_err(|e| relocate(e, input, start)))
+}
+
+/// Transform a "bucket of items" returned from the parser into an ExitList struct.
+///
+/// Everything from an `ExitNode` item until the next one belongs to the same relay.
+fn transmogrify<'a>(annotation: TypeAnnotation<'a>, item_bucket: Vec<Item<'a>>) -> ExitList<'a> {
+    let mut el = ExitList { type_annotation: Some(annotation), ..Default::default() };
+    let mut nodes: Vec<Vec<Item>> = Vec::new();
+
+    for item in item_bucket {
+        match item.key {
+            "ExitNode" => nodes.push(vec![item]),
+            _ if !nodes.is_empty() => {
+                // safe to unwrap, nodes is not empty
+                nodes.last_mut().unwrap().push(item);
+            }
+            "Downloaded" => singleton_arg!(item => el.downloaded),
+            _ => {
+                el.unprocessed_items.push(item);
+            }
+        }
+    }
+    el.exit_nodes = nodes.into_iter().map(transmogrify_exit_node).collect();
+    el
+}
+
+/// Transform the items of a single relay into an ExitNode struct.
+fn transmogrify_exit_node<'a>(item_bucket: Vec<Item<'a>>) -> ExitNode<'a> {
+    let mut en: ExitNode = Default::default();
+
+    for item in item_bucket {
+        match item.key {
+            "Published"  => singleton_arg!(item => en.published),
+            "LastStatus" => singleton_arg!(item => en.last_status),
+
+            "ExitNode" => {
+                if let (Some(fingerprint), 0) = (item.args.and_then(Digest::from_fingerprint), item.objs.len()) {
+                    en.fingerprint = Some(fingerprint);
+                } else {
+                    en.unprocessed_items.push(item);
+                }
+            }
+
+            "ExitAddress" => use_parser!(item => en, exit_address, |r| en.exit_addresses.push(r)),
+
+            _ => {
+                en.unprocessed_items.push(item);
+            }
+        }
+    }
+    en
+}
+
+
+named!(exit_list_bucket <(TypeAnnotation<'a>, Vec<Item<'a>>)>,
+    chain!(
+        annotation: map_opt!(type_annotation, accepted_as(DescriptorType::ExitList)) ~
+        items:      many1!(item) ,
+        || { (annotation, items) }
+    )
+);
+// "ExitAddress" SP IPv4 SP YYYY-MM-DD HH:MM:SS NL
+//
+//    [Any number, once per observed address.]
+named!(exit_address <ExitAddress<'a>>,
+    chain!(
+        address:  ipv4_addr ~
+                  space ~
+        observed: timestamp ,
+        || { ExitAddress { address, observed } }
+    )
+);
@@ -0,0 +1,373 @@
+//! Relay Extra-Info Descriptors (`@type extra-info 1.0`).
+//!
+//! Extra-info documents contain information about a relay which is not needed by clients for
+//! building circuits, mostly statistics such as bandwidth history and directory request counts.
+//! A relay's server descriptor points to its extra-info document via `extra_info_digest`.
+//!
+//! Sanitized bridge extra-info descriptors (`@type bridge-extra-info 1.3`) are parsed the same
+//! way.
+
+use std::str;
+use nom::{alphanumeric, hex_digit, space};
+use nom::IResult;
+
+use document::*;
+use digest::{Digest, signed_portion};
+use grammar::*;
+use ParseError;
+use error::{finish, relocate, Syntax};
+
+/// Common data from a parsed extra-info descriptor.
+#[derive(Default, Debug)]
+pub struct ExtraInfoDescriptor<'a> {
+    /// The `@type` annotation the descriptor was prefixed with, which records the version of the
+    /// format.  Descriptors from Tor's own data directory have none.
+    pub type_annotation: Option<TypeAnnotation<'a>>,
+
+    /// Any other annotations the descriptor was prefixed with, in the order they appear.
+    pub annotations: Vec<Annotation<'a>>,
+
+    /// Router nickname.
+    pub nickname: &'a str,
+
+    /// Hex-encoded digest of the router's identity key, with no spaces.
+    pub fingerprint: &'a str,
+
+    /// The base64-encoded Ed25519 certificate, as for the server descriptor.  When present, it
+    /// MUST appear as the first or second element in the document.
+    pub identity_ed25519: Option<&'a str>,
+
+    /// The time, in UTC, when this document (and its corresponding server descriptor) was
+    /// generated.  It MUST match the published time in the corresponding server descriptor.
+    ///
+    /// The format for the time is `YYYY-MM-DD HH:MM:SS`.
+    pub published: Option<&'a str>,
+
+    /// Number of bytes read by the router over a series of fixed-length intervals.
+    pub read_history: Option<History<'a>>,
+
+    /// Number of bytes written by the router over a series of fixed-length intervals.
+    pub write_history: Option<History<'a>>,
+
+    /// Number of bytes read by the router when answering directory requests.
+    pub dirreq_read_history: Option<History<'a>>,
+
+    /// Number of bytes written by the router when answering directory requests.
+    pub dirreq_write_history: Option<History<'a>>,
+
+    /// SHA1 digest of the IPv4 GeoIP database file that is used to resolve IPv4 addresses to
+    /// country codes.
+    pub geoip_db_digest: Option<&'a str>,
+
+    /// SHA1 digest of the IPv6 GeoIP database file that is used to resolve IPv6 addresses to
+    /// country codes.
+    pub geoip6_db_digest: Option<&'a str>,
+
+    /// End of the interval for which the directory request statistics were collected.
+    pub dirreq_stats_end: Option<StatsInterval<'a>>,
+
+    /// Approximate number of unique IP addresses, by country code, that have connected to this
+    /// directory to request a v3 network status.
+    pub dirreq_v3_ips: Option<Counts<'a>>,
+
+    /// Number of v3 network status requests, by country code.
+    pub dirreq_v3_reqs: Option<Counts<'a>>,
+
+    /// Number of v3 network status requests, by the status of the response.
+    pub dirreq_v3_resp: Option<Counts<'a>>,
+
+    /// Statistics about the v3 network status downloads made directly to this directory.
+    pub dirreq_v3_direct_dl: Option<Counts<'a>>,
+
+    /// Statistics about the v3 network status downloads made via tunneled connections.
+    pub dirreq_v3_tunneled_dl: Option<Counts<'a>>,
+
+    /// End of the interval for which the entry guard statistics were collected.
+    pub entry_stats_end: Option<StatsInterval<'a>>,
+
+    /// Approximate number of unique IP addresses, by country code, that have connected to this
+    /// relay as an entry guard.
+    pub entry_ips: Option<Counts<'a>>,
+
+    /// End of the interval for which the exit statistics were collected.
+    pub exit_stats_end: Option<StatsInterval<'a>>,
+
+    /// Number of kibibytes written by the exit, by destination port.
+    pub exit_kibibytes_written: Option<Counts<'a>>,
+
+    /// Number of kibibytes read by the exit, by destination port.
+    pub exit_kibibytes_read: Option<Counts<'a>>,
+
+    /// Number of opened exit streams, by destination port.
+    pub exit_streams_opened: Option<Counts<'a>>,
+
+    /// End of the interval for which the bridge statistics were collected.
+    pub bridge_stats_end: Option<StatsInterval<'a>>,
+
+    /// Approximate number of unique IP addresses, by country code, that have connected to this
+    /// bridge.
+    pub bridge_ips: Option<Counts<'a>>,
+
+    /// Approximate number of unique IP addresses that have connected to this bridge, by IP
+    /// version.
+    pub bridge_ip_versions: Option<Counts<'a>>,
+
+    /// Approximate number of unique IP addresses that have connected to this bridge, by
+    /// pluggable transport.
+    pub bridge_ip_transports: Option<Counts<'a>>,
+
+    /// Ed25519 signature of the document, as for the server descriptor.
+    pub router_sig_ed25519: Option<&'a str>,
+
+    /// The `SIGNATURE` object contains a signature of the PKCS1-padded hash of the entire
+    /// extra-info document, made with the router's identity key.
+    pub router_signature: Option<&'a str>,
+
+    /// The pluggable transports supported by a bridge, as `transportname [address:port [args]]`.
+    /// (Bridges only.)
+    pub transports: Vec<&'a str>,
+
+    /// The hex-encoded SHA1 digest of the original, unsanitized bridge extra-info descriptor.
+    /// Sanitized bridge descriptors include this in place of a `router_signature`.  (Bridges only.)
+    pub router_digest: Option<&'a str>,
+
+    /// The base64-encoded SHA256 digest of the original, unsanitized bridge extra-info
+    /// descriptor, with trailing `=`s removed.  (Bridges only.)
+    pub router_digest_sha256: Option<&'a str>,
+
+    /// Items we have successfully parsed from an ExtraInfoDescriptor, but have not been
+    /// processed into structured data.
+    ///
+    /// See `ServerDescriptor::unprocessed_items` for details.
+    pub unprocessed_items: Vec<Item<'a>>,
+
+    /// The text of the descriptor, from `extra-info` until the end.
+    text: &'a str,
+}
+
+impl<'a> ExtraInfoDescriptor<'a> {
+    /// The SHA1 digest of the signed portion of the descriptor, from `extra-info` up to and
+    /// including the `router-signature` line.
+    ///
+    /// This is the first digest of the `extra_info_digest` of the relay's server descriptor, in
+    /// hex.  Sanitized bridge descriptors have no signature, see `router_digest` instead.
+    pub fn digest_sha1(&self) -> Option<Digest> {
+        signed_portion(self.text).map(|signed| Digest::sha1(signed.as_bytes()))
+    }
+
+    /// The SHA256 digest of the entire descriptor, including its signature.
+    ///
+    /// This is the second digest of the `extra_info_digest` of the relay's server descriptor, in
+    /// base64.
+    pub fn digest_sha256(&self) -> Digest {
+        Digest::sha256(self.text.as_bytes())
+    }
+}
+
+/// Measurements collected over a series of fixed-length intervals.
+#[derive(Debug, PartialEq)]
+pub struct History<'a> {
+    /// The end time of the most recent interval, as `YYYY-MM-DD HH:MM:SS`.
+    pub end: &'a str,
+    /// Length of each interval, in seconds.
+    pub interval: u64,
+    /// Measured values, from the oldest interval to the most recent.
+    pub values: Vec<u64>,
+}
+
+/// The end time of the period over which some statistics were collected.
+#[derive(Debug, PartialEq)]
+pub struct StatsInterval<'a> {
+    /// The end time of the interval, as `YYYY-MM-DD HH:MM:SS`.
+    pub end: &'a str,
+    /// Length of the interval, in seconds.
+    pub interval: u64,
+}
+
+/// An order-preserving list of `key=count` pairs, such as counts per country code or per port.
+pub type Counts<'a> = Vec<(&'a str, u64)>;
+
+pub fn parse(input: &str) -> Result<ExtraInfoDescriptor<'_>, ParseError> {
+    let (ta, annotations, (text, ei)) = finish(input, extra_info_bucket(input.as_bytes()), Syntax::KeywordLines)?;
+    Ok(transmogrify(ta, annotations, text, ei))
+}
+
+/// Parse all of the extra-info descriptors in `input`, skipping any which are malformed.
+pub fn parse_all(input: &str) -> Vec<ExtraInfoDescriptor<'_>> {
+    parse_each(input).filter_map(Result::ok).collect()
+}
+
+/// Parse each of the extra-info descriptors in `input` in turn, resuming at the next one after
+/// any which are malformed.
+///
+/// See `server_descriptor::parse_each`.
+pub fn parse_each(input: &str) -> impl Iterator<Item = Result<ExtraInfoDescriptor<'_>, ParseError>> {
+    documents(input, "extra-info").map(move |(start, text)| parse(text).map_err(|e| relocate(e, input, start)))
+}
+
+/// Transform a "bucket of items" returned from the parser into an ExtraInfoDescriptor struct.
+fn transmogrify<'a>(type_annotation: Option<TypeAnnotation<'a>>,
+                    annotations: Vec<Annotation<'a>>,
+                    text: &'a str,
+                    item_bucket: Vec<Item<'a>>) -> ExtraInfoDescriptor<'a> {
+    let mut ei = ExtraInfoDescriptor { type_annotation, annotations, text, ..Default::default() };
+
+    for item in item_bucket {
+        // the various statistics lists are allowed to be entirely empty, in which case there will
+        // be no args on the KeywordLine at all.
+        macro_rules! counts_arg { (.$field:ident) => {{
+            if let (None, 0) = (item.args, item.objs.len()) {
+                ei.$field = Some(Vec::new());
+                continue;
+            }
+            use_whole_parser!(item => ei, counts, |r| ei.$field = Some(r))
+        }}}
+
+        match item.key {
+            "identity-ed25519"      => first_obj!(item => ei.identity_ed25519),
+            "published"             => singleton_arg!(item => ei.published),
+            "geoip-db-digest"       => singleton_arg!(item => ei.geoip_db_digest),
+            "geoip6-db-digest"      => singleton_arg!(item => ei.geoip6_db_digest),
+            "router-sig-ed25519"    => singleton_arg!(item => ei.router_sig_ed25519),
+            "router-signature"      => first_obj!(item => ei.router_signature),
+            "router-digest"         => singleton_arg!(item => ei.router_digest),
+            "router-digest-sha256"  => singleton_arg!(item => ei.router_digest_sha256),
+
+            "transport" => {
+                if let (Some(args), 0) = (item.args, item.objs.len()) {
+                    ei.transports.push(args);
+                } else {
+                    ei.unprocessed_items.push(item);
+                }
+            }
+
+            "extra-info" => use_parser!(item => ei, extra_info, |(nickname, fingerprint)| {
+                ei.nickname    = nickname;
+                ei.fingerprint = fingerprint;
+            }),
+
+            "read-history"          => use_whole_parser!(item => ei, history, |r| ei.read_history = Some(r)),
+            "write-history"         => use_whole_parser!(item => ei, history, |r| ei.write_history = Some(r)),
+            "dirreq-read-history"   => use_whole_parser!(item => ei, history, |r| ei.dirreq_read_history = Some(r)),
+            "dirreq-write-history"  => use_whole_parser!(item => ei, history, |r| ei.dirreq_write_history = Some(r)),
+
+            "dirreq-stats-end"      => use_parser!(item => ei, stats_interval, |r| ei.dirreq_stats_end = Some(r)),
+            "entry-stats-end"       => use_parser!(item => ei, stats_interval, |r| ei.entry_stats_end = Some(r)),
+            "exit-stats-end"        => use_parser!(item => ei, stats_interval, |r| ei.exit_stats_end = Some(r)),
+            "bridge-stats-end"      => use_parser!(item => ei, stats_interval, |r| ei.bridge_stats_end = Some(r)),
+
+            "dirreq-v3-ips"          => counts_arg!(.dirreq_v3_ips),
+            "dirreq-v3-reqs"         => counts_arg!(.dirreq_v3_reqs),
+            "dirreq-v3-resp"         => counts_arg!(.dirreq_v3_resp),
+            "dirreq-v3-direct-dl"    => counts_arg!(.dirreq_v3_direct_dl),
+            "dirreq-v3-tunneled-dl"  => counts_arg!(.dirreq_v3_tunneled_dl),
+            "entry-ips"              => counts_arg!(.entry_ips),
+            "exit-kibibytes-written" => counts_arg!(.exit_kibibytes_written),
+            "exit-kibibytes-read"    => counts_arg!(.exit_kibibytes_read),
+            "exit-streams-opened"    => counts_arg!(.exit_streams_opened),
+            "bridge-ips"             => counts_arg!(.bridge_ips),
+            "bridge-ip-versions"     => counts_arg!(.bridge_ip_versions),
+            "bridge-ip-transports"   => counts_arg!(.bridge_ip_transports),
+
+            _ => {
+                ei.unprocessed_items.push(item);
+            }
+        }
+    }
+    ei
+}
+
+
+/// The annotations, text and items of an extra-info descriptor, as returned from the parser.
+type Bucket<'a> = (Option<TypeAnnotation<'a>>, Vec<Annotation<'a>>, (&'a str, Vec<Item<'a>>));
+
+named!(extra_info_bucket <Bucket<'a>>,
+    chain!(
+        ta:          opt!(complete!(map_opt!(type_annotation, accepted_as(DescriptorType::ExtraInfo)))) ~
+        annotations: many0!(annotation) ~
+                     peek!(tag!("extra-info ")) ~
+        items:       items_with_text ,
+        || { (ta, annotations, items) }
+    )
+);
+
+// "extra-info" Nickname Fingerprint NL
+//
+//    [At start, exactly once.]
+//
+//    Identifies what router this is an extra-info descriptor for.
+//    Fingerprint is encoded in hex (using upper-case letters), with
+//    no spaces.
+named!(extra_info <&[u8], (&str, &str)>,
+    chain!(
+        nickname:    map_res!(alphanumeric, str::from_utf8) ~
+                     space ~
+        fingerprint: map_res!(hex_digit, str::from_utf8) ,
+        || { (nickname, fingerprint) }
+    )
+);
+
+// "read-history" YYYY-MM-DD HH:MM:SS (NSEC s) NUM,NUM,NUM,NUM,NUM... NL
+// "write-history" YYYY-MM-DD HH:MM:SS (NSEC s) NUM,NUM,NUM,NUM,NUM... NL
+//
+//    [At most once]
+//
+//    Declare how much bandwidth the OR has used recently. Usage is divided
+//    into intervals of NSEC seconds.  The YYYY-MM-DD HH:MM:SS field
+//    defines the end of the most recent interval.  The numbers are the
+//    number of bytes used in the most recent intervals, ordered from
+//    oldest to newest.
+named!(history <History<'a>>,
+    chain!(
+        interval: stats_interval ~
+        values:   opt!(complete!(
+                      chain!(space ~ v: separated_list!(tag!(","), u64_digit), || { v })
+                  )) ,
+        || { History{
+            end: interval.end,
+            interval: interval.interval,
+            values: values.unwrap_or_default(),
+        } }
+    )
+);
+
+// "dirreq-stats-end" YYYY-MM-DD HH:MM:SS (NSEC s) NL
+//
+//    [At most once.]
+//
+//    YYYY-MM-DD HH:MM:SS defines the end of the included measurement
+//    interval of length NSEC seconds (86400 seconds by default).
+//
+// (and likewise for the other "*-stats-end" items.)
+named!(stats_interval <StatsInterval<'a>>,
+    chain!(
+        end:      timestamp ~
+                  space ~
+                  tag!("(") ~
+        interval: u64_digit ~
+                  space ~
+                  tag!("s)") ,
+        || { StatsInterval{ end, interval } }
+    )
+);
+
+// "dirreq-v3-ips" CC=NUM,CC=NUM,... NL
+//
+//    [At most once.]
+//
+//    List of mappings from two-letter country codes to the number of
+//    unique IP addresses that have connected from that country to
+//    request a v3 network status, rounded up to the nearest multiple of 8.
+//
+// (and likewise for the other lists of `key=count` pairs.)
+named!(counts <Counts<'a>>,
+    separated_list!(tag!(","), count)
+);
+named!(count <(&'a str, u64)>,
+    chain!(
+        key:   map_res!(is_not!("=,"), str::from_utf8) ~
+               tag!("=") ~
+        value: u64_digit ,
+        || { (key, value) }
+    )
+);
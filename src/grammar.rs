@@ -10,7 +10,7 @@ use std::str;
 use std::str::FromStr;
 use std::net::Ipv4Addr;
 
-use nom::{digit};
+use nom::{digit, space};
 
 named!(pub ipv4_addr <Ipv4Addr>,
     chain!(
@@ -47,3 +47,48 @@ named!(pub u64_digit<u64>,
         FromStr::from_str
     )
 );
+
+// Timestamps throughout the directory protocol are of the form `YYYY-MM-DD HH:MM:SS`, in UTC.
+//
+// Since Rust does not have a standard datetime in the stdlib (yet), these are just recognized and
+// left as ASCII, so that the consumer of this library can pick their desired time representation.
+named!(pub timestamp <&str>,
+    map_res!(
+        recognize!(
+            chain!(
+                digit ~ tag!("-") ~ digit ~ tag!("-") ~ digit ~
+                space ~
+                digit ~ tag!(":") ~ digit ~ tag!(":") ~ digit ,
+                || {}
+            )
+        ),
+        str::from_utf8
+    )
+);
+
+named!(pub i64_digit<i64>,
+    map_res!(
+        map_res!(recognize!(chain!(opt!(tag!("-")) ~ digit, || {})), str::from_utf8),
+        FromStr::from_str
+    )
+);
+
+// A base64-encoded value appearing as an argument, such as a digest, with any trailing `=`s.
+named!(pub base64_token <&str>,
+    map_res!(
+        is_a!("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/="),
+        str::from_utf8
+    )
+);
+
+// A `Key=Value` pair, as used by the formats which don't follow the keyword line convention of
+// the directory protocol, such as bandwidth files and torperf results.  Neither part may contain
+// spaces.
+named!(pub key_value <(&str, &str)>,
+    chain!(
+        key:   map_res!(is_not!("= \r\n"), str::from_utf8) ~
+               tag!("=") ~
+        value: map_res!(is_not!(" \r\n"), str::from_utf8) ,
+        || { (key, value) }
+    )
+);
@@ -0,0 +1,8 @@
+//! Onion Service Descriptors.
+//!
+//! Onion services (formerly hidden services) publish descriptors to the HSDir relays, through
+//! which clients learn how to reach them.  Each version of the protocol has its own descriptor
+//! format, and so gets its own module here.
+
+pub mod v2;
+pub mod v3;
@@ -0,0 +1,203 @@
+//! Version 2 Rendezvous Service Descriptors (`@type hidden-service-descriptor 1.0`).
+//!
+//! The introduction points of a service are included in the descriptor as a base64-encoded
+//! document of their own, which may additionally be encrypted when client authorization is used.
+
+use std::str;
+use std::net::Ipv4Addr;
+use nom::IResult;
+
+use document::*;
+use grammar::*;
+use ParseError;
+use error::{finish, relocate, Syntax};
+
+/// Common data from a parsed v2 rendezvous service descriptor.
+#[derive(Default, Debug)]
+pub struct HsDescriptor<'a> {
+    /// The `@type` annotation the descriptor was prefixed with, which records the version of the
+    /// format.
+    pub type_annotation: Option<TypeAnnotation<'a>>,
+
+    /// The identifier of this descriptor, encoded in base32.
+    pub descriptor_id: Option<&'a str>,
+
+    /// The version of the descriptor format, `2`.
+    pub version: Option<u64>,
+
+    /// The public key of the service, wrapped in `-----BEGIN RSA PUBLIC KEY-----` and
+    /// `-----END RSA PUBLIC KEY-----`.
+    pub permanent_key: Option<&'a str>,
+
+    /// The hash of the time period, descriptor cookie and replica number, encoded in base32.
+    pub secret_id_part: Option<&'a str>,
+
+    /// The time, in UTC, when this descriptor was generated, as `YYYY-MM-DD HH:MM:SS`.
+    pub published: Option<&'a str>,
+
+    /// The versions of the rendezvous protocol supported by the service.
+    pub protocol_versions: Vec<u64>,
+
+    /// The decoded contents of the `introduction-points` object.  Unless client authorization is
+    /// in use, these can be parsed with `introduction_points()`.
+    pub introduction_points: Option<Vec<u8>>,
+
+    /// The `SIGNATURE` object contains a signature of the PKCS1-padded hash of the whole
+    /// descriptor, made with the service's permanent key.
+    pub signature: Option<&'a str>,
+
+    /// Items we have successfully parsed from a HsDescriptor, but have not been processed into
+    /// structured data.
+    ///
+    /// See `ServerDescriptor::unprocessed_items` for details.
+    pub unprocessed_items: Vec<Item<'a>>,
+}
+
+/// An introduction point of a service, through which clients can contact it.
+#[derive(Default, Debug)]
+pub struct IntroductionPoint<'a> {
+    /// The identity digest of the introduction point relay, encoded in base32.
+    pub identifier: &'a str,
+
+    /// IPv4 network address of the introduction point relay.
+    pub address: Option<Ipv4Addr>,
+
+    /// Port at which the introduction point relay accepts TLS connections.
+    pub port: Option<u16>,
+
+    /// The onion key of the introduction point relay.
+    pub onion_key: Option<&'a str>,
+
+    /// The public key the service uses for this introduction point.
+    pub service_key: Option<&'a str>,
+
+    /// Items we have successfully parsed from an IntroductionPoint, but have not been processed
+    /// into structured data.
+    ///
+    /// See `ServerDescriptor::unprocessed_items` for details.
+    pub unprocessed_items: Vec<Item<'a>>,
+}
+
+impl<'a> HsDescriptor<'a> {
+    /// Parse the decoded `introduction_points` into a list of IntroductionPoint structs.
+    ///
+    /// Returns `None` if there are no introduction points, or if they are encrypted.
+    pub fn introduction_points(&self) -> Option<Vec<IntroductionPoint<'_>>> {
+        let text = match self.introduction_points {
+            Some(ref bytes) => str::from_utf8(bytes).ok()?,
+            None => return None,
+        };
+        match introduction_points_items(text.as_bytes()) {
+            IResult::Done(b"", items) => Some(split_introduction_points(items)),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse(input: &str) -> Result<HsDescriptor<'_>, ParseError> {
+    let (annotation, hsd) = finish(input, hs_descriptor_bucket(input.as_bytes()), Syntax::KeywordLines)?;
+    Ok(transmogrify(annotation, hsd))
+}
+
+/// Parse all of the descriptors in `input`, skipping any which are malformed.
+pub fn parse_all(input: &str) -> Vec<HsDescriptor<'_>> {
+    parse_each(input).filter_map(Result::ok).collect()
+}
+
+/// Parse each of the descriptors in `input` in turn, resuming at the next one after any which
+/// are malformed.
+///
+/// See `server_descriptor::parse_each`.
+pub fn parse_each(input: &str) -> impl Iterator<Item = Result<HsDescriptor<'_>, ParseError>> {
+    documents(input, "rendezvous-service-descriptor")
+        .map(move |(start, text)| parse(text).map_err(|e| relocate(e, input, start)))
+}
+
+/// Transform a "bucket of items" returned from the parser into a HsDescriptor struct.
+fn transmogrify<'a>(annotation: TypeAnnotation<'a>, item_bucket: Vec<Item<'a>>) -> HsDescriptor<'a> {
+    let mut hsd = HsDescriptor { type_annotation: Some(annotation), ..Default::default() };
+
+    for item in item_bucket {
+        match item.key {
+            "rendezvous-service-descriptor" => singleton_arg!(item => hsd.descriptor_id),
+            "permanent-key"                 => first_obj!(item => hsd.permanent_key),
+            "secret-id-part"                => singleton_arg!(item => hsd.secret_id_part),
+            "publication-time"              => singleton_arg!(item => hsd.published),
+            "introduction-points"           => decoded_obj!(item => hsd.introduction_points),
+            "signature"                     => first_obj!(item => hsd.signature),
+
+            "version" => use_parser!(item => hsd, u64_digit, |r| hsd.version = Some(r)),
+
+            "protocol-versions" => use_parser!(item => hsd, protocol_versions, |r| {
+                hsd.protocol_versions = r
+            }),
+
+            _ => {
+                hsd.unprocessed_items.push(item);
+            }
+        }
+    }
+    hsd
+}
+
+/// Split the items of the introduction points document at each `introduction-point` item, and
+/// transform them into IntroductionPoint structs.
+fn split_introduction_points(item_bucket: Vec<Item>) -> Vec<IntroductionPoint> {
+    let mut buckets: Vec<Vec<Item>> = Vec::new();
+    for item in item_bucket {
+        if item.key == "introduction-point" || buckets.is_empty() {
+            buckets.push(Vec::new());
+        }
+        // safe to unwrap, a bucket was pushed above if there were none
+        buckets.last_mut().unwrap().push(item);
+    }
+    buckets.into_iter().map(transmogrify_introduction_point).collect()
+}
+
+/// Transform the items of a single introduction point into an IntroductionPoint struct.
+fn transmogrify_introduction_point<'a>(item_bucket: Vec<Item<'a>>) -> IntroductionPoint<'a> {
+    let mut ip: IntroductionPoint = Default::default();
+
+    for item in item_bucket {
+        match item.key {
+            "onion-key"   => first_obj!(item => ip.onion_key),
+            "service-key" => first_obj!(item => ip.service_key),
+
+            "introduction-point" => {
+                if let (Some(args), 0) = (item.args, item.objs.len()) {
+                    ip.identifier = args;
+                } else {
+                    ip.unprocessed_items.push(item);
+                }
+            }
+
+            "ip-address" => use_parser!(item => ip, ipv4_addr, |r| ip.address = Some(r)),
+            "onion-port" => use_parser!(item => ip, u16_digit, |r| ip.port = Some(r)),
+
+            _ => {
+                ip.unprocessed_items.push(item);
+            }
+        }
+    }
+    ip
+}
+
+
+named!(hs_descriptor_bucket <(TypeAnnotation<'a>, Vec<Item<'a>>)>,
+    chain!(
+        annotation: map_opt!(type_annotation, accepted_as(DescriptorType::HsDescriptorV2)) ~
+        items:      many1!(item) ,
+        || { (annotation, items) }
+    )
+);
+named!(introduction_points_items < Vec<Item<'a>> >, many1!(item));
+
+// "protocol-versions" SP version-string NL
+//
+//    [Exactly once.]
+//
+//    A comma-separated list of recognized and permitted version numbers
+//    for use in INTRODUCE cells.
+named!(protocol_versions < Vec<u64> >,
+    separated_nonempty_list!(tag!(","), u64_digit)
+);
@@ -0,0 +1,111 @@
+//! Version 3 Onion Service Descriptors (`@type hidden-service-descriptor-3 1.0`).
+//!
+//! Only the outer layer of the descriptor is parsed here.  Everything interesting about the
+//! service is inside the `superencrypted` blob, which first needs to be decrypted using the
+//! service's blinded public key (and, for the inner layer, possibly client authorization keys).
+//!
+//! Descriptors are accepted with or without the CollecTor `@type` annotation, so that the ones
+//! fetched directly from an HSDir can be parsed as well.
+
+use nom::IResult;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+
+use document::*;
+use grammar::*;
+use ParseError;
+use error::{finish, relocate, Syntax};
+
+/// The outer layer of a parsed v3 onion service descriptor.
+#[derive(Default, Debug)]
+pub struct HsDescriptor<'a> {
+    /// The `@type` annotation the descriptor was prefixed with, if any, which records the version
+    /// of the format.
+    pub type_annotation: Option<TypeAnnotation<'a>>,
+
+    /// The version of the descriptor format, `3`.
+    pub version: Option<u64>,
+
+    /// The lifetime of the descriptor, in minutes.
+    pub lifetime: Option<u64>,
+
+    /// The decoded Ed25519 certificate (see `cert-spec.txt`) of the short-term descriptor
+    /// signing key, signed by the blinded public key of the service.
+    pub signing_key_cert: Option<Vec<u8>>,
+
+    /// An increasing counter, so that HSDirs can tell which of two descriptors is the newer.
+    pub revision_counter: Option<u64>,
+
+    /// The decoded, still encrypted, first layer of the descriptor.
+    pub superencrypted: Option<Vec<u8>>,
+
+    /// The decoded Ed25519 signature of the whole descriptor, made with the descriptor signing
+    /// key.
+    pub signature: Option<Vec<u8>>,
+
+    /// Items we have successfully parsed from a HsDescriptor, but have not been processed into
+    /// structured data.
+    ///
+    /// See `ServerDescriptor::unprocessed_items` for details.
+    pub unprocessed_items: Vec<Item<'a>>,
+}
+
+pub fn parse(input: &str) -> Result<HsDescriptor<'_>, ParseError> {
+    let (annotation, hsd) = finish(input, hs_descriptor_bucket(input.as_bytes()), Syntax::KeywordLines)?;
+    Ok(transmogrify(annotation, hsd))
+}
+
+/// Parse all of the descriptors in `input`, skipping any which are malformed.
+pub fn parse_all(input: &str) -> Vec<HsDescriptor<'_>> {
+    parse_each(input).filter_map(Result::ok).collect()
+}
+
+/// Parse each of the descriptors in `input` in turn, resuming at the next one after any which
+/// are malformed.
+///
+/// See `server_descriptor::parse_each`.
+pub fn parse_each(input: &str) -> impl Iterator<Item = Result<HsDescriptor<'_>, ParseError>> {
+    documents(input, "hs-descriptor").map(move |(start, text)| parse(text).map_err(|e| relocate(e, input, start)))
+}
+
+/// Transform a "bucket of items" returned from the parser into a HsDescriptor struct.
+fn transmogrify<'a>(annotation: Option<TypeAnnotation<'a>>, item_bucket: Vec<Item<'a>>) -> HsDescriptor<'a> {
+    let mut hsd = HsDescriptor { type_annotation: annotation, ..Default::default() };
+
+    for item in item_bucket {
+        match item.key {
+            "descriptor-signing-key-cert" => decoded_obj!(item => hsd.signing_key_cert),
+            "superencrypted"              => decoded_obj!(item => hsd.superencrypted),
+
+            "hs-descriptor"       => use_parser!(item => hsd, u64_digit, |r| hsd.version = Some(r)),
+            "descriptor-lifetime" => use_parser!(item => hsd, u64_digit, |r| hsd.lifetime = Some(r)),
+            "revision-counter"    => use_parser!(item => hsd, u64_digit, |r| hsd.revision_counter = Some(r)),
+
+            // the signature is base64 encoded with trailing `=`s removed, like other ed25519
+            // values in the directory protocol.
+            "signature" => {
+                if let (Some(args), 0) = (item.args, item.objs.len()) {
+                    if let Ok(sig) = STANDARD_NO_PAD.decode(args) {
+                        hsd.signature = Some(sig);
+                        continue;
+                    }
+                }
+                hsd.unprocessed_items.push(item);
+            }
+
+            _ => {
+                hsd.unprocessed_items.push(item);
+            }
+        }
+    }
+    hsd
+}
+
+
+named!(hs_descriptor_bucket <(Option<TypeAnnotation<'a>>, Vec<Item<'a>>)>,
+    chain!(
+        annotation: opt!(complete!(map_opt!(type_annotation, accepted_as(DescriptorType::HsDescriptorV3)))) ~
+        items:      many1!(item) ,
+        || { (annotation, items) }
+    )
+);
@@ -1,7 +1,48 @@
 #[macro_use]
 extern crate nom;
+extern crate sha1;
+extern crate sha2;
+extern crate base64;
+#[cfg(feature = "archive")]
+extern crate tar;
+#[cfg(feature = "archive")]
+extern crate flate2;
+#[cfg(feature = "archive")]
+extern crate xz2;
+#[cfg(feature = "archive")]
+extern crate bzip2;
+#[cfg(feature = "verify")]
+extern crate rsa;
+#[cfg(feature = "verify")]
+extern crate ed25519_dalek;
+#[cfg(feature = "verify")]
+extern crate curve25519_dalek;
 
+#[macro_use]
+mod macros;
+mod error;
 mod grammar;
 
 pub mod document;
+#[cfg(feature = "archive")]
+pub mod archive;
+pub mod cert;
+pub mod descriptor;
+pub mod data_directory;
+pub mod digest;
+pub mod bandwidth_file;
+pub mod dir_key_certificate;
+pub mod exit_list;
+pub mod extra_info;
+pub mod hidden_service;
+pub mod microdescriptor;
+pub mod network_status;
+pub mod reader;
 pub mod server_descriptor;
+pub mod torperf;
+#[cfg(feature = "verify")]
+pub mod verify;
+
+pub use descriptor::{Descriptor, parse_any, parse_each_any};
+pub use error::{ParseError, ErrorKind};
+pub use reader::DescriptorReader;
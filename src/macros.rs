@@ -0,0 +1,90 @@
+//! Helper macros shared by the various `transmogrify` functions, which transform a "bucket of
+//! items" returned from a document parser into its structured representation.
+//!
+//! All of these expect to be expanded inside the `for item in item_bucket` loop, and take the
+//! name of the current `Item` binding as well as the struct being populated. Anything that does
+//! not match the expected shape ends up in the struct's `unprocessed_items`.
+
+// common pattern for an Item with a KeywordLine consisting of one REQUIRED arg that is simply
+// treated as a blob of text, with no additional processing required, just store it in `$field`.
+macro_rules! singleton_arg { ($item:ident => $doc:ident . $field:ident) => {{
+    if let (Some(args), 0) = ($item.args, $item.objs.len()) {
+        $doc.$field = Some(args);
+    } else {
+        $doc.unprocessed_items.push($item);
+    }
+}}}
+
+// common pattern for an Item that contains exactly one object (& no args), which will be simply
+// treated as a blob of text, with no additional processing required, just store it in `$field`.
+macro_rules! first_obj { ($item:ident => $doc:ident . $field:ident) => {{
+    if (None, 1) == ($item.args, $item.objs.len()) {
+        $doc.$field = Some($item.objs[0]); //safe because of above len() check
+    } else {
+        $doc.unprocessed_items.push($item);
+    }
+}}}
+
+// common pattern for an Item that contains exactly one object (& no args), which is kept along
+// with its object type keyword as an `Object` in `$field`.
+macro_rules! typed_obj { ($item:ident => $doc:ident . $field:ident) => {{
+    if (None, 1) == ($item.args, $item.objs.len()) {
+        if let IResult::Done(_, obj) = typed_object($item.objs[0].as_bytes()) {
+            $doc.$field = Some(obj);
+            continue;
+        }
+    }
+    $doc.unprocessed_items.push($item);
+}}}
+
+// common pattern for an Item that contains exactly one object (& no args), the base64-encoded
+// contents of which are decoded into bytes in `$field`.
+macro_rules! decoded_obj { ($item:ident => $doc:ident . $field:ident) => {{
+    if (None, 1) == ($item.args, $item.objs.len()) {
+        if let IResult::Done(_, obj) = typed_object($item.objs[0].as_bytes()) {
+            if let Some(bytes) = obj.decode() {
+                $doc.$field = Some(bytes);
+                continue;
+            }
+        }
+    }
+    $doc.unprocessed_items.push($item);
+}}}
+
+// common pattern for an Item where the KeywordLine args will parsed with an additional Nom
+// parser.  Takes the identifier of the parser, and a closure which will function as the results
+// handler for the return value of a successful parse.
+//
+// If the parser fails for any reason (error, incompete data), the Item is merely added to the
+// unprocessed_items list.
+macro_rules! use_parser { ($item:ident => $doc:ident, $parser:ident, $results_handler:expr) => {{
+    if let Some(args) = $item.args {
+        if let IResult::Done(_, res) = $parser(args.as_bytes()) {
+            $results_handler(res);
+            continue;
+        }
+    }
+    $doc.unprocessed_items.push($item);
+}}}
+
+// like `use_parser!`, but the parser has to consume all of the KeywordLine args, for parsers
+// such as comma-separated lists which would otherwise stop quietly at the first bad element.
+macro_rules! use_whole_parser { ($item:ident => $doc:ident, $parser:ident, $results_handler:expr) => {{
+    if let Some(args) = $item.args {
+        if let IResult::Done(b"", res) = $parser(args.as_bytes()) {
+            $results_handler(res);
+            continue;
+        }
+    }
+    $doc.unprocessed_items.push($item);
+}}}
+
+// common pattern for an Item with a KeywordLine consisting of a (possibly empty) list of
+// space-separated words, such as a list of flags, which are collected into a `Vec` in `$field`.
+macro_rules! words_arg { ($item:ident => $doc:ident . $field:ident) => {{
+    if $item.objs.is_empty() {
+        $doc.$field = $item.args.map_or(Vec::new(), |args| args.split_whitespace().collect());
+    } else {
+        $doc.unprocessed_items.push($item);
+    }
+}}}
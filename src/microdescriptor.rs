@@ -0,0 +1,168 @@
+//! Microdescriptors (`@type microdescriptor 1.0`).
+//!
+//! Microdescriptors are a stripped down version of server descriptors, containing only the
+//! information clients need to build circuits.  Relays don't publish them themselves, the
+//! directory authorities generate them from the server descriptors.
+//!
+//! Microdescriptors carry no identifying information on their own; they are referred to from
+//! the router status entries of a microdescriptor consensus by their digest.
+
+use std::str;
+use std::collections::HashMap;
+use nom::{space, rest};
+use nom::IResult;
+
+use document::*;
+use digest::Digest;
+use server_descriptor::exit_policy::*;
+use ParseError;
+use error::{finish, relocate, Syntax};
+
+/// Common data from a parsed microdescriptor.
+#[derive(Default, Debug)]
+pub struct Microdescriptor<'a> {
+    /// The `@type` annotation the descriptor was prefixed with, which records the version of the
+    /// format.  Descriptors from Tor's own data directory have none.
+    pub type_annotation: Option<TypeAnnotation<'a>>,
+
+    /// Any other annotations the descriptor was prefixed with, such as `@last-listed` in
+    /// Tor's cached microdescriptor files, in the order they appear.
+    pub annotations: Vec<Annotation<'a>>,
+
+    /// The TAP key used to encrypt CREATE cells for this OR, as for the server descriptor.
+    pub onion_key: Option<&'a str>,
+
+    /// The curve25519 public key used for the ntor circuit extended handshake, encoded in base64.
+    pub ntor_onion_key: Option<&'a str>,
+
+    /// Additional addresses (usually IPv6) at which this OR accepts connections, as `address:port`.
+    pub or_addresses: Vec<&'a str>,
+
+    /// The family of the OR, as a list of nicknames and `$`-prefixed hex-encoded identity digests.
+    pub family: Vec<&'a str>,
+
+    /// Summary of the router's IPv4 exit policy.
+    pub exit_policy: Option<PolicySummary>,
+
+    /// Summary of the router's IPv6 exit policy.
+    pub exit_policy_v6: Option<PolicySummary>,
+
+    /// The router's RSA identity, as a base64-encoded digest of the identity key, with trailing
+    /// `=`s removed.
+    pub identity_rsa1024: Option<&'a str>,
+
+    /// The router's ed25519 identity key, encoded in base64 with trailing `=`s removed.
+    pub identity_ed25519: Option<&'a str>,
+
+    /// Items we have successfully parsed from a Microdescriptor, but have not been processed into
+    /// structured data.
+    ///
+    /// See `ServerDescriptor::unprocessed_items` for details.
+    pub unprocessed_items: Vec<Item<'a>>,
+
+    /// The text of the microdescriptor, from `onion-key` until the end.
+    text: &'a str,
+}
+
+impl<'a> Microdescriptor<'a> {
+    /// The SHA256 digest of the microdescriptor.
+    ///
+    /// This is how the microdescriptor is referred to by the `m` line of a router status entry in
+    /// a microdescriptor consensus, encoded in base64.
+    pub fn digest(&self) -> Digest {
+        Digest::sha256(self.text.as_bytes())
+    }
+}
+
+pub fn parse(input: &str) -> Result<Microdescriptor<'_>, ParseError> {
+    let (ta, annotations, (text, items)) = finish(input, microdescriptor_bucket(input.as_bytes()), Syntax::KeywordLines)?;
+    Ok(transmogrify(ta, annotations, text, items))
+}
+
+/// Parse all of the microdescriptors in `input`, skipping any which are malformed.
+pub fn parse_all(input: &str) -> Vec<Microdescriptor<'_>> {
+    parse_each(input).filter_map(Result::ok).collect()
+}
+
+/// Parse each of the microdescriptors in `input` in turn, resuming at the next one after any
+/// which are malformed.
+///
+/// See `server_descriptor::parse_each`.
+pub fn parse_each(input: &str) -> impl Iterator<Item = Result<Microdescriptor<'_>, ParseError>> {
+    documents(input, "onion-key").map(move |(start, text)| parse(text).map_err(|e| relocate(e, input, start)))
+}
+
+/// Index microdescriptors by their `digest()`, so that they can be looked up from the router
+/// status entries of a microdescriptor consensus via `RouterStatus::microdesc_digest()`.
+pub fn index_by_digest<'a>(mds: Vec<Microdescriptor<'a>>) -> HashMap<Digest, Microdescriptor<'a>> {
+    mds.into_iter().map(|md| (md.digest(), md)).collect()
+}
+
+/// Transform a "bucket of items" returned from the parser into a Microdescriptor struct.
+fn transmogrify<'a>(type_annotation: Option<TypeAnnotation<'a>>,
+                    annotations: Vec<Annotation<'a>>,
+                    text: &'a str,
+                    item_bucket: Vec<Item<'a>>) -> Microdescriptor<'a> {
+    let mut md = Microdescriptor { type_annotation, annotations, text, ..Default::default() };
+
+    for item in item_bucket {
+        match item.key {
+            "onion-key"      => first_obj!(item => md.onion_key),
+            "ntor-onion-key" => singleton_arg!(item => md.ntor_onion_key),
+            "family"         => words_arg!(item => md.family),
+
+            "a" => {
+                if let (Some(args), 0) = (item.args, item.objs.len()) {
+                    md.or_addresses.push(args);
+                } else {
+                    md.unprocessed_items.push(item);
+                }
+            }
+
+            "p"  => use_parser!(item => md, parse_policy_summary, |r| md.exit_policy = Some(r)),
+            "p6" => use_parser!(item => md, parse_policy_summary, |r| md.exit_policy_v6 = Some(r)),
+
+            "id" => use_parser!(item => md, identity, |(key_type, key)| {
+                match key_type {
+                    "rsa1024" => md.identity_rsa1024 = Some(key),
+                    _         => md.identity_ed25519 = Some(key),
+                }
+            }),
+
+            _ => {
+                md.unprocessed_items.push(item);
+            }
+        }
+    }
+    md
+}
+
+
+/// The annotations, text and items of a microdescriptor, as returned from the parser.
+type Bucket<'a> = (Option<TypeAnnotation<'a>>, Vec<Annotation<'a>>, (&'a str, Vec<Item<'a>>));
+
+named!(microdescriptor_bucket <Bucket<'a>>,
+    chain!(
+        ta:          opt!(complete!(map_opt!(type_annotation, accepted_as(DescriptorType::Microdescriptor)))) ~
+        annotations: many0!(annotation) ~
+                     peek!(tag!("onion-key")) ~
+        items:       items_with_text ,
+        || { (ta, annotations, items) }
+    )
+);
+
+// "id" SP "rsa1024" SP base64-encoded-identity-digest NL
+// "id" SP "ed25519" SP base64-encoded-ed25519-identity NL
+//
+//    [At most once per distinct type.]
+//
+//    The "id" items are used to identify the relay in a way that can't
+//    be forged by the relay itself.
+named!(identity <&[u8], (&str, &str)>,
+    chain!(
+        key_type: map_res!(alt!(tag!("rsa1024") | tag!("ed25519")), str::from_utf8) ~
+                  space ~
+        key:      map_res!(rest, str::from_utf8) ,
+        || { (key_type, key) }
+    )
+);
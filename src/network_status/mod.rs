@@ -0,0 +1,482 @@
+//! Network Status Consensus Documents (`@type network-status-consensus-3 1.0`).
+//!
+//! A consensus is the document produced by the directory authorities every hour, describing the
+//! state of the whole Tor network.  It consists of a preamble (the `Header`), a section
+//! describing each of the directory authorities which contributed to it, a status entry for
+//! every router in the network, and a footer with the signatures of the authorities.
+//!
+//! Microdescriptor consensuses (`@type network-status-microdesc-consensus-3 1.0`) share the same
+//! structure, and are parsed with `parse_microdesc`.
+//!
+//! The sanitized bridge network statuses published by CollecTor (`@type bridge-network-status
+//! 1.2`) consist of only a short header and router status entries, and are parsed with
+//! `parse_bridge`.
+//!
+//! The votes from which a consensus is computed share the same general layout, and are parsed by
+//! the `vote` module.
+
+use std::str;
+use std::net::Ipv4Addr;
+use nom::{alphanumeric, space};
+use nom::IResult;
+
+pub mod router_status;
+use self::router_status::RouterStatus;
+
+pub mod vote;
+
+use document::*;
+use grammar::*;
+use ParseError;
+use error::{finish, Syntax};
+
+/// A parsed network status consensus document.
+#[derive(Default, Debug)]
+pub struct Consensus<'a> {
+    /// The `@type` annotation the document was prefixed with, which records the flavor of the
+    /// consensus and the version of the format.  Consensuses cached by Tor have none.
+    pub type_annotation: Option<TypeAnnotation<'a>>,
+    /// General information about the consensus.
+    pub header: Header<'a>,
+    /// The directory authorities whose votes contributed to the consensus.
+    pub authorities: Vec<Authority<'a>>,
+    /// A status entry for each router listed in the consensus, ordered by identity.
+    pub routers: Vec<RouterStatus<'a>>,
+    /// Signatures of the directory authorities over the consensus.
+    pub footer: Footer<'a>,
+}
+
+/// The preamble of a network status document.
+#[derive(Default, Debug)]
+pub struct Header<'a> {
+    /// The version of the network status format, currently `3`.
+    pub network_status_version: Option<&'a str>,
+
+    /// Either `consensus` or `vote`.
+    pub vote_status: Option<&'a str>,
+
+    /// The consensus method used to generate this consensus.
+    pub consensus_method: Option<u64>,
+
+    /// The consensus methods supported by the authority which produced a vote.
+    pub consensus_methods: Vec<u64>,
+
+    /// The time at which a vote was published, as `YYYY-MM-DD HH:MM:SS`.
+    pub published: Option<&'a str>,
+
+    /// The time at which the consensus was approved, as `YYYY-MM-DD HH:MM:SS`.
+    pub valid_after: Option<&'a str>,
+
+    /// The time at which the next consensus should be produced, as `YYYY-MM-DD HH:MM:SS`.
+    pub fresh_until: Option<&'a str>,
+
+    /// The time after which clients should no longer use this consensus, as
+    /// `YYYY-MM-DD HH:MM:SS`.
+    pub valid_until: Option<&'a str>,
+
+    /// The number of seconds the authorities allowed for collecting votes, and then for
+    /// collecting signatures, respectively.
+    pub voting_delay: Option<(u64, u64)>,
+
+    /// A comma-separated list of versions of Tor recommended for use as clients.
+    pub client_versions: Option<&'a str>,
+
+    /// A comma-separated list of versions of Tor recommended for use as relays.
+    pub server_versions: Option<&'a str>,
+
+    /// All the flags that may be assigned to routers in the router status entries.
+    pub known_flags: Vec<&'a str>,
+
+    /// Internal performance thresholds used by an authority to assign flags in its vote, as
+    /// `key=value` pairs.  The values are left as strings, since some of them are percentages.
+    pub flag_thresholds: Vec<(&'a str, &'a str)>,
+
+    /// Consensus parameters, which allow the authorities to tune the behavior of clients and
+    /// relays on the network.
+    pub params: Params<'a>,
+
+    /// Items we have successfully parsed from a Header, but have not been processed into
+    /// structured data.
+    ///
+    /// See `ServerDescriptor::unprocessed_items` for details.
+    pub unprocessed_items: Vec<Item<'a>>,
+}
+
+/// A directory authority, as listed in a network status document.
+#[derive(Default, Debug)]
+pub struct Authority<'a> {
+    /// The authority's nickname.
+    pub nickname: &'a str,
+
+    /// Hex-encoded digest of the authority's identity key.
+    pub identity: &'a str,
+
+    /// The authority's hostname.
+    pub hostname: &'a str,
+
+    /// IPv4 network address of the authority.
+    pub address: Option<Ipv4Addr>,
+
+    /// Port at which the authority accepts directory-related HTTP connections.
+    pub dir_port: u16,
+
+    /// Port at which the authority accepts TLS connections for the main OR protocol.
+    pub or_port: u16,
+
+    /// Contact information for the authority's operator.
+    pub contact: Option<&'a str>,
+
+    /// Digest of the vote this authority submitted for the consensus, encoded in hex.
+    pub vote_digest: Option<&'a str>,
+
+    /// Items we have successfully parsed from an Authority, but have not been processed into
+    /// structured data.
+    ///
+    /// See `ServerDescriptor::unprocessed_items` for details.
+    pub unprocessed_items: Vec<Item<'a>>,
+}
+
+/// The footer of a network status document.
+#[derive(Default, Debug)]
+pub struct Footer<'a> {
+    /// Weights used by clients to balance the selection of routers for each position in a path.
+    pub bandwidth_weights: Params<'a>,
+
+    /// Signatures of the directory authorities over the document.
+    pub signatures: Vec<DirectorySignature<'a>>,
+
+    /// Items we have successfully parsed from a Footer, but have not been processed into
+    /// structured data.
+    ///
+    /// See `ServerDescriptor::unprocessed_items` for details.
+    pub unprocessed_items: Vec<Item<'a>>,
+}
+
+/// A signature of a directory authority over a network status document.
+#[derive(Debug, PartialEq)]
+pub struct DirectorySignature<'a> {
+    /// The digest algorithm used for the signature, either `sha1` (the default if not specified
+    /// in the document) or `sha256`.
+    pub algorithm: &'a str,
+
+    /// Hex-encoded digest of the authority's identity key.
+    pub identity: &'a str,
+
+    /// Hex-encoded digest of the authority's signing key.
+    pub signing_key_digest: &'a str,
+
+    /// The `SIGNATURE` object.
+    pub signature: &'a str,
+}
+
+/// An order-preserving list of `Keyword=Int32` pairs, such as the consensus parameters.
+pub type Params<'a> = Vec<(&'a str, i64)>;
+
+pub fn parse(input: &str) -> Result<Consensus<'_>, ParseError> {
+    let (annotation, items) = finish(input, consensus_bucket(input.as_bytes()), Syntax::KeywordLines)?;
+    Ok(transmogrify(annotation, items))
+}
+
+/// Parse a microdescriptor consensus, in which the router status entries refer to the
+/// microdescriptor of each router rather than its server descriptor.
+pub fn parse_microdesc(input: &str) -> Result<Consensus<'_>, ParseError> {
+    let (annotation, items) = finish(input, microdesc_consensus_bucket(input.as_bytes()), Syntax::KeywordLines)?;
+    Ok(transmogrify(annotation, items))
+}
+
+/// Parse a sanitized bridge network status, as published by the bridge authority.
+///
+/// These have no authority sections or footer, and the nicknames, addresses and identities of
+/// the router status entries are hashed or scrubbed.
+pub fn parse_bridge(input: &str) -> Result<Consensus<'_>, ParseError> {
+    let (annotation, items) = finish(input, bridge_network_status_bucket(input.as_bytes()), Syntax::KeywordLines)?;
+    Ok(transmogrify(annotation, items))
+}
+
+/// Transform a "bucket of items" returned from the parser into a Consensus struct.
+fn transmogrify<'a>(type_annotation: Option<TypeAnnotation<'a>>, item_bucket: Vec<Item<'a>>) -> Consensus<'a> {
+    let sections = split_sections(item_bucket);
+    Consensus {
+        type_annotation,
+        header:          transmogrify_header(sections.header),
+        authorities:     sections.authorities.into_iter().map(transmogrify_authority).collect(),
+        routers:         sections.routers.into_iter().map(router_status::transmogrify).collect(),
+        footer:          transmogrify_footer(sections.footer),
+    }
+}
+
+/// The items of a network status document, split into buckets for each of its sections.
+struct Sections<'a> {
+    header: Vec<Item<'a>>,
+    authorities: Vec<Vec<Item<'a>>>,
+    routers: Vec<Vec<Item<'a>>>,
+    footer: Vec<Item<'a>>,
+}
+
+/// Split the items of a network status document into its sections.
+///
+/// Each authority section begins with a `dir-source` item and each router status entry with an
+/// `r` item.  The footer begins with `directory-footer`, or with the first item that may only
+/// appear in the footer for documents generated by older consensus methods.
+fn split_sections(item_bucket: Vec<Item>) -> Sections {
+    let mut sections = Sections {
+        header: Vec::new(),
+        authorities: Vec::new(),
+        routers: Vec::new(),
+        footer: Vec::new(),
+    };
+
+    #[derive(PartialEq)]
+    enum Position { Header, Authority, Router, Footer }
+    let mut pos = Position::Header;
+
+    for item in item_bucket {
+        if pos != Position::Footer {
+            match item.key {
+                "dir-source" => {
+                    pos = Position::Authority;
+                    sections.authorities.push(Vec::new());
+                }
+                "r" => {
+                    pos = Position::Router;
+                    sections.routers.push(Vec::new());
+                }
+                "directory-footer" | "bandwidth-weights" | "directory-signature" => {
+                    pos = Position::Footer;
+                }
+                _ => {}
+            }
+        }
+
+        match pos {
+            Position::Header    => sections.header.push(item),
+            Position::Footer    => sections.footer.push(item),
+            // safe to unwrap, a new bucket is always pushed when entering these sections
+            Position::Authority => sections.authorities.last_mut().unwrap().push(item),
+            Position::Router    => sections.routers.last_mut().unwrap().push(item),
+        }
+    }
+    sections
+}
+
+/// Transform the header items of a network status document into a Header struct.
+fn transmogrify_header<'a>(item_bucket: Vec<Item<'a>>) -> Header<'a> {
+    let mut h: Header = Default::default();
+
+    for item in item_bucket {
+        match item.key {
+            "network-status-version" => singleton_arg!(item => h.network_status_version),
+            "vote-status"            => singleton_arg!(item => h.vote_status),
+            "published"              => singleton_arg!(item => h.published),
+            "valid-after"            => singleton_arg!(item => h.valid_after),
+            "fresh-until"            => singleton_arg!(item => h.fresh_until),
+            "valid-until"            => singleton_arg!(item => h.valid_until),
+            "client-versions"        => singleton_arg!(item => h.client_versions),
+            "server-versions"        => singleton_arg!(item => h.server_versions),
+            "known-flags"            => words_arg!(item => h.known_flags),
+
+            "consensus-method" => use_parser!(item => h, u64_digit, |r| h.consensus_method = Some(r)),
+
+            "consensus-methods" => use_parser!(item => h, consensus_methods, |r| h.consensus_methods = r),
+
+            "flag-thresholds" => use_parser!(item => h, flag_thresholds, |r| h.flag_thresholds = r),
+
+            "voting-delay" => use_parser!(item => h, voting_delay, |r| h.voting_delay = Some(r)),
+
+            "params" => {
+                if let (None, 0) = (item.args, item.objs.len()) {
+                    continue;
+                }
+                use_parser!(item => h, params, |r| h.params = r)
+            }
+
+            _ => {
+                h.unprocessed_items.push(item);
+            }
+        }
+    }
+    h
+}
+
+/// Transform the items of a single authority section into an Authority struct.
+fn transmogrify_authority<'a>(item_bucket: Vec<Item<'a>>) -> Authority<'a> {
+    let mut a: Authority = Default::default();
+
+    for item in item_bucket {
+        match item.key {
+            "contact"     => singleton_arg!(item => a.contact),
+            "vote-digest" => singleton_arg!(item => a.vote_digest),
+
+            "dir-source" => use_parser!(item => a, dir_source, |r| {
+                let (nickname, identity, hostname, address, dir_port, or_port) = r;
+                a.nickname = nickname;
+                a.identity = identity;
+                a.hostname = hostname;
+                a.address  = Some(address);
+                a.dir_port = dir_port;
+                a.or_port  = or_port;
+            }),
+
+            _ => {
+                a.unprocessed_items.push(item);
+            }
+        }
+    }
+    a
+}
+
+/// Transform the footer items of a network status document into a Footer struct.
+fn transmogrify_footer<'a>(item_bucket: Vec<Item<'a>>) -> Footer<'a> {
+    let mut f: Footer = Default::default();
+
+    for item in item_bucket {
+        match item.key {
+            // only a marker for the start of the footer, nothing to process
+            "directory-footer" if item.args.is_none() => {}
+
+            "bandwidth-weights" => use_parser!(item => f, params, |r| f.bandwidth_weights = r),
+
+            "directory-signature" => {
+                let args: Vec<&str> = item.args.map_or(Vec::new(), |a| a.split_whitespace().collect());
+                let (algorithm, identity, signing_key_digest) = match args.len() {
+                    2 => ("sha1", args[0], args[1]),
+                    3 => (args[0], args[1], args[2]),
+                    _ => { f.unprocessed_items.push(item); continue; }
+                };
+                if item.objs.len() != 1 {
+                    f.unprocessed_items.push(item);
+                    continue;
+                }
+                f.signatures.push(DirectorySignature {
+                    algorithm,
+                    identity,
+                    signing_key_digest,
+                    signature: item.objs[0],
+                });
+            }
+
+            _ => {
+                f.unprocessed_items.push(item);
+            }
+        }
+    }
+    f
+}
+
+
+// For each flavor, any minor version of the supported major version is accepted.  The cached
+// consensuses in Tor's data directory have no annotation at all.
+named!(consensus_bucket <(Option<TypeAnnotation<'a>>, Vec<Item<'a>>)>,
+    chain!(
+        annotation: opt!(complete!(map_opt!(type_annotation, accepted_as(DescriptorType::Consensus)))) ~
+        items:      many1!(item) ,
+        || { (annotation, items) }
+    )
+);
+named!(microdesc_consensus_bucket <(Option<TypeAnnotation<'a>>, Vec<Item<'a>>)>,
+    chain!(
+        annotation: opt!(complete!(map_opt!(type_annotation, accepted_as(DescriptorType::MicrodescConsensus)))) ~
+        items:      many1!(item) ,
+        || { (annotation, items) }
+    )
+);
+named!(bridge_network_status_bucket <(Option<TypeAnnotation<'a>>, Vec<Item<'a>>)>,
+    chain!(
+        annotation: opt!(complete!(map_opt!(type_annotation, accepted_as(DescriptorType::BridgeNetworkStatus)))) ~
+        items:      many1!(item) ,
+        || { (annotation, items) }
+    )
+);
+
+// "voting-delay" SP VoteSeconds SP DistSeconds NL
+//
+//    [Exactly once.]
+//
+//    VoteSeconds is the number of seconds that we will allow to collect
+//    votes from all authorities; DistSeconds is the number of seconds
+//    we'll allow to collect signatures from all authorities.
+named!(voting_delay <(u64, u64)>,
+    chain!(
+        vote: u64_digit ~
+              space ~
+        dist: u64_digit ,
+        || { (vote, dist) }
+    )
+);
+
+// "consensus-methods" SP IntegerList NL
+//
+//    [At most once for votes; does not occur in consensuses.]
+//
+//    A space-separated list of supported methods for generating
+//    consensuses from votes.
+named!(consensus_methods < Vec<u64> >,
+    separated_nonempty_list!(space, u64_digit)
+);
+
+// "flag-thresholds" SP Thresholds NL
+//
+//    [At most once for votes; does not occur in consensuses.]
+//
+//    A space-separated list of the internal performance thresholds
+//    that the directory authority had at the moment it was forming
+//    a vote.
+//
+//    The metaformat is:
+//
+//       Thresholds = Threshold | Threshold SP Thresholds
+//       Threshold = ThresholdKey '=' ThresholdVal
+//       ThresholdKey = (KeywordChar | "_") +
+//       ThresholdVal = [0-9]+("."[0-9]+)? "%"?
+named!(flag_thresholds < Vec<(&'a str, &'a str)> >,
+    separated_list!(space,
+        chain!(
+            key:   map_res!(is_not!("= "), str::from_utf8) ~
+                   tag!("=") ~
+            value: map_res!(is_not!(" "), str::from_utf8) ,
+            || { (key, value) }
+        )
+    )
+);
+
+// "params" SP [Parameters] NL
+//
+//    [At most once]
+//
+//    Parameter ::= Keyword '=' Int32
+//    Int32 ::= A decimal integer between -2147483648 and 2147483647.
+//    Parameters ::= Parameter | Parameters SP Parameter
+//
+// (the "bandwidth-weights" in the footer share the same format.)
+named!(params <Params<'a>>,
+    separated_list!(space,
+        chain!(
+            key:   map_res!(is_not!("= "), str::from_utf8) ~
+                   tag!("=") ~
+            value: i64_digit ,
+            || { (key, value) }
+        )
+    )
+);
+
+// "dir-source" SP nickname SP identity SP address SP IP SP dirport SP orport NL
+//
+//    [Exactly once, at start]
+//
+//    Describes this authority.  The nickname is a convenient identifier
+//    for the authority.  The identity is an uppercase hex fingerprint of
+//    the authority's current (v3 authority) identity key.  The address is
+//    the server's hostname.  The IP is the server's current IP address,
+//    and dirport is its current directory port.  The orport is the
+//    server's current OR port.
+named!(dir_source <&[u8], (&str, &str, &str, Ipv4Addr, u16, u16)>,
+    chain!(
+        nickname: map_res!(alphanumeric, str::from_utf8) ~ space ~
+        identity: map_res!(is_not!(" "), str::from_utf8) ~ space ~
+        hostname: map_res!(is_not!(" "), str::from_utf8) ~ space ~
+        address:  ipv4_addr ~ space ~
+        dir_port: u16_digit ~ space ~
+        or_port:  u16_digit ,
+        || { (nickname, identity, hostname, address, dir_port, or_port) }
+    )
+);
@@ -0,0 +1,239 @@
+//! Router status entries, the per-relay section of a network status document.
+//!
+//! The same entry format is shared (with small variations) between consensuses, votes, and
+//! microdescriptor consensuses.
+
+use std::str;
+use std::net::Ipv4Addr;
+use nom::{alphanumeric, space};
+use nom::IResult;
+
+use document::*;
+use digest::Digest;
+use grammar::*;
+use server_descriptor::exit_policy::*;
+
+/// The status of a single router, as listed in a network status document.
+#[derive(Default, Debug)]
+pub struct RouterStatus<'a> {
+    /// Router nickname.
+    pub nickname: &'a str,
+
+    /// Hash of the router's identity key, encoded in base64 with trailing `=`s removed.
+    pub identity: &'a str,
+
+    /// Hash of the router's most recent server descriptor, encoded in base64 with trailing `=`s
+    /// removed.
+    ///
+    /// Microdescriptor consensuses do not include this, see `microdesc_digests` instead.
+    pub digest: Option<&'a str>,
+
+    /// The publication time of the router's most recent descriptor, as `YYYY-MM-DD HH:MM:SS`.
+    pub published: &'a str,
+
+    /// IPv4 network address for the OR.
+    pub address: Option<Ipv4Addr>,
+
+    /// Port at which this OR accepts TLS connections for the main OR protocol.
+    pub or_port: u16,
+
+    /// Port at which this OR accepts directory-related HTTP connections.
+    pub dir_port: u16,
+
+    /// Additional addresses (usually IPv6) at which this OR accepts connections, as `address:port`.
+    pub or_addresses: Vec<&'a str>,
+
+    /// Flags assigned to the router by the directory authorities, such as `Running` or `Exit`.
+    pub flags: Vec<&'a str>,
+
+    /// The version of the Tor protocol that this relay is running, usually a platform string
+    /// such as `Tor 0.2.8.6`.
+    pub version: Option<&'a str>,
+
+    /// The subprotocol versions supported by the router, as a space-separated list of
+    /// `Keyword=Values` entries.
+    pub protocols: Option<&'a str>,
+
+    /// An estimate of the bandwidth of this relay, in an arbitrary unit (currently kilobytes per
+    /// second), used for load balancing.
+    pub bandwidth: Option<u64>,
+
+    /// The bandwidth measured by a bandwidth authority for this relay (only present in votes).
+    pub measured: Option<u64>,
+
+    /// Whether the `bandwidth` value was not based on at least three bandwidth measurements.
+    pub unmeasured: bool,
+
+    /// Summary of the router's exit policy.
+    pub exit_policy: Option<PolicySummary>,
+
+    /// The router's ed25519 identity key, encoded in base64 with trailing `=`s removed, or `none`
+    /// if the router has no ed25519 identity (only present in votes).
+    pub ed25519_id: Option<&'a str>,
+
+    /// The `m` lines, listing the digest of the router's microdescriptor for a range of consensus
+    /// methods (in votes), or just the digest itself (in microdescriptor consensuses).
+    pub microdesc_digests: Vec<&'a str>,
+
+    /// Items we have successfully parsed from a RouterStatus, but have not been processed into
+    /// structured data.
+    ///
+    /// See `ServerDescriptor::unprocessed_items` for details.
+    pub unprocessed_items: Vec<Item<'a>>,
+}
+
+impl<'a> RouterStatus<'a> {
+    /// The digest of the router's microdescriptor, as listed in a microdescriptor consensus, or
+    /// `None` if there is none or it isn't valid base64.
+    ///
+    /// This can be used to look up the matching microdescriptor by its `digest()`.
+    pub fn microdesc_digest(&self) -> Option<Digest> {
+        match self.microdesc_digests.first() {
+            Some(m) if self.digest.is_none() => Digest::from_base64(m),
+            _ => None,
+        }
+    }
+}
+
+/// Transform a "bucket of items" for a single router into a RouterStatus struct.
+#[doc(hidden)]
+pub fn transmogrify<'a>(item_bucket: Vec<Item<'a>>) -> RouterStatus<'a> {
+    let mut rs: RouterStatus = Default::default();
+
+    for item in item_bucket {
+        match item.key {
+            "v"  => singleton_arg!(item => rs.version),
+            "pr" => singleton_arg!(item => rs.protocols),
+            "s"  => words_arg!(item => rs.flags),
+
+            "r" => use_parser!(item => rs, router, |r| {
+                let (nickname, identity, digest, published, address, or_port, dir_port) = r;
+                rs.nickname  = nickname;
+                rs.identity  = identity;
+                rs.digest    = digest;
+                rs.published = published;
+                rs.address   = Some(address);
+                rs.or_port   = or_port;
+                rs.dir_port  = dir_port;
+            }),
+
+            "a" => {
+                if let (Some(args), 0) = (item.args, item.objs.len()) {
+                    rs.or_addresses.push(args);
+                } else {
+                    rs.unprocessed_items.push(item);
+                }
+            }
+
+            "m" => {
+                if let (Some(args), 0) = (item.args, item.objs.len()) {
+                    rs.microdesc_digests.push(args);
+                } else {
+                    rs.unprocessed_items.push(item);
+                }
+            }
+
+            "w" => use_parser!(item => rs, weights, |r: Vec<(&str, u64)>| {
+                for (key, value) in r {
+                    match key {
+                        "Bandwidth"  => rs.bandwidth  = Some(value),
+                        "Measured"   => rs.measured   = Some(value),
+                        "Unmeasured" => rs.unmeasured = value == 1,
+                        _ => {}
+                    }
+                }
+            }),
+
+            "id" => use_parser!(item => rs, ed25519_id, |r| rs.ed25519_id = Some(r)),
+
+            "p" => use_parser!(item => rs, parse_policy_summary, |r| rs.exit_policy = Some(r)),
+
+            _ => {
+                rs.unprocessed_items.push(item);
+            }
+        }
+    }
+    rs
+}
+
+// "r" SP nickname SP identity SP digest SP publication SP IP SP ORPort SP DirPort NL
+//
+//    [At start, exactly once.]
+//
+//    "Nickname" is the OR's nickname.  "Identity" is a hash of its
+//    identity key, encoded in base64, with trailing equals sign(s)
+//    removed.  "Digest" is a hash of its most recent descriptor as
+//    signed (that is, not including the signature), encoded in base64.
+//    "Publication" is the publication time of its most recent descriptor,
+//    in the form YYYY-MM-DD HH:MM:SS, in UTC.  "IP" is its current IP
+//    address; ORPort is its current OR port, "DirPort" is its current
+//    directory port, or "0" for "none".
+//
+// Microdescriptor consensuses omit the "digest" field.
+named!(router <&[u8], (&str, &str, Option<&str>, &str, Ipv4Addr, u16, u16)>,
+    alt_complete!(
+        chain!(
+            nickname:  map_res!(alphanumeric, str::from_utf8) ~ space ~
+            identity:  base64_token ~ space ~
+            digest:    base64_token ~ space ~
+            published: timestamp ~ space ~
+            address:   ipv4_addr ~ space ~
+            or_port:   u16_digit ~ space ~
+            dir_port:  u16_digit ,
+            || { (nickname, identity, Some(digest), published, address, or_port, dir_port) }
+        ) |
+        chain!(
+            nickname:  map_res!(alphanumeric, str::from_utf8) ~ space ~
+            identity:  base64_token ~ space ~
+            published: timestamp ~ space ~
+            address:   ipv4_addr ~ space ~
+            or_port:   u16_digit ~ space ~
+            dir_port:  u16_digit ,
+            || { (nickname, identity, None, published, address, or_port, dir_port) }
+        )
+    )
+);
+
+// "w" SP "Bandwidth=" INT [SP "Measured=" INT] [SP "Unmeasured=1"] NL
+//
+//    [At most once.]
+named!(weights <Vec<(&'a str, u64)> >,
+    separated_list!(space,
+        chain!(
+            key:   map_res!(is_not!("= "), str::from_utf8) ~
+                   tag!("=") ~
+            value: u64_digit ,
+            || { (key, value) }
+        )
+    )
+);
+
+// "id" SP "ed25519" SP ed25519-identity NL
+// "id" SP "ed25519" SP "none" NL
+//
+//    [vote only, at most once]
+named!(ed25519_id <&'a str>,
+    chain!(
+            tag!("ed25519") ~
+            space ~
+        id: map_res!(is_not!(" "), str::from_utf8) ,
+        || { id }
+    )
+);
+
+#[test]
+fn test_router() {
+    let (_, r) = router(
+        b"seele AAoQ1DAR6kkoo19hBAX5K0QztNw oX3I/XP4/WB/bXpc7XJ3UVuXrLU 2016-08-05 21:10:27 67.161.31.147 9001 0"
+    ).unwrap();
+    assert_eq!(r.0, "seele");
+    assert_eq!(r.2, Some("oX3I/XP4/WB/bXpc7XJ3UVuXrLU"));
+    assert_eq!(r.3, "2016-08-05 21:10:27");
+
+    // microdescriptor consensus flavor, with no descriptor digest
+    let (_, r) = router(
+        b"seele AAoQ1DAR6kkoo19hBAX5K0QztNw 2016-08-05 21:10:27 67.161.31.147 9001 0"
+    ).unwrap();
+    assert_eq!(r.2, None);
+    assert_eq!(r.4, Ipv4Addr::new(67,161,31,147));
+}
@@ -0,0 +1,68 @@
+//! Network Status Vote Documents (`@type network-status-vote-3 1.0`).
+//!
+//! A vote is the opinion of a single directory authority about the state of the network, from
+//! which the consensus is computed.  Unlike a consensus, it describes only the authority which
+//! produced it, and embeds that authority's key certificate.
+
+
+use document::*;
+use ParseError;
+use error::{finish, Syntax};
+use super::*;
+use super::router_status::RouterStatus;
+use dir_key_certificate::{self, KeyCertificate};
+
+/// A parsed network status vote document.
+#[derive(Default, Debug)]
+pub struct Vote<'a> {
+    /// The `@type` annotation the document was prefixed with, which records the version of the
+    /// format.
+    pub type_annotation: Option<TypeAnnotation<'a>>,
+    /// General information about the vote, including the `flag-thresholds` of the authority.
+    pub header: Header<'a>,
+    /// The directory authority which produced the vote.
+    pub authority: Authority<'a>,
+    /// The key certificate of the authority, certifying the key the vote is signed with.
+    pub key_certificate: KeyCertificate<'a>,
+    /// A status entry for each router the authority has an opinion about, ordered by identity.
+    ///
+    /// Entries in a vote contain the `Measured` bandwidth values from the bandwidth authorities.
+    pub routers: Vec<RouterStatus<'a>>,
+    /// The signature of the authority over the vote.
+    pub footer: Footer<'a>,
+}
+
+pub fn parse(input: &str) -> Result<Vote<'_>, ParseError> {
+    let (annotation, items) = finish(input, vote_bucket(input.as_bytes()), Syntax::KeywordLines)?;
+    Ok(transmogrify(annotation, items))
+}
+
+/// Transform a "bucket of items" returned from the parser into a Vote struct.
+fn transmogrify<'a>(annotation: TypeAnnotation<'a>, item_bucket: Vec<Item<'a>>) -> Vote<'a> {
+    let sections = split_sections(item_bucket);
+
+    // a vote only ever contains one authority section, with the key certificate embedded in it.
+    let mut authority: Vec<Item> = sections.authorities.into_iter().flatten().collect();
+    let key_certificate = match authority.iter().position(|i| i.key == "dir-key-certificate-version") {
+        Some(pos) => authority.split_off(pos),
+        None      => Vec::new(),
+    };
+
+    Vote {
+        type_annotation: Some(annotation),
+        header:          transmogrify_header(sections.header),
+        authority:       transmogrify_authority(authority),
+        key_certificate: dir_key_certificate::transmogrify(key_certificate),
+        routers:         sections.routers.into_iter().map(router_status::transmogrify).collect(),
+        footer:          transmogrify_footer(sections.footer),
+    }
+}
+
+
+named!(vote_bucket <(TypeAnnotation<'a>, Vec<Item<'a>>)>,
+    chain!(
+        annotation: map_opt!(type_annotation, accepted_as(DescriptorType::Vote)) ~
+        items:      many1!(item) ,
+        || { (annotation, items) }
+    )
+);
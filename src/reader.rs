@@ -0,0 +1,83 @@
+//! Reading descriptors one at a time from a stream, such as a large CollecTor file.
+//!
+//! The parsers borrow from the text they are given, so rather than parsed descriptors the reader
+//! yields the text of each one, to be handed to the matching `parse` function (or `parse_any`).
+//! Only a single descriptor is held in memory at a time.
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use std::io::BufReader;
+//! use tordesc::{server_descriptor, DescriptorReader};
+//!
+//! let file = File::open("2016-08-server-descriptors").unwrap();
+//! for text in DescriptorReader::new(BufReader::new(file), "router") {
+//!     match server_descriptor::parse(&text.unwrap()) {
+//!         Ok(sd) => println!("{}", sd.nickname),
+//!         Err(e) => println!("skipping malformed descriptor: {}", e),
+//!     }
+//! }
+//! ```
+
+use std::io::{self, BufRead};
+use std::mem;
+
+use document::starts_document;
+
+/// An iterator over the text of each descriptor read from `R`.
+///
+/// Descriptors are split the same way as `document::documents` does: a new one starts at any
+/// annotation following the body of the previous one, or at an item with the keyword the reader
+/// was created with, such as `router` for server descriptors or `onion-key` for microdescriptors.
+///
+/// A descriptor that isn't valid UTF-8, such as one with a Latin-1 `contact` line, is returned as
+/// an `InvalidData` error in its place, and reading carries on with the next one.
+pub struct DescriptorReader<R> {
+    inner: R,
+    keyword: String,
+    // the first line of the next descriptor, which has already been read
+    pending: Vec<u8>,
+}
+
+impl<R: BufRead> DescriptorReader<R> {
+    /// Read descriptors from `inner`, each of which starts with an item with `keyword` unless
+    /// it is annotated.
+    pub fn new(inner: R, keyword: &str) -> DescriptorReader<R> {
+        DescriptorReader { inner, keyword: keyword.to_string(), pending: Vec::new() }
+    }
+
+    /// Get back the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: BufRead> Iterator for DescriptorReader<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<io::Result<String>> {
+        let mut text = mem::take(&mut self.pending);
+        let mut in_body = !text.is_empty() && !text.starts_with(b"@");
+        loop {
+            // descriptors are framed on the raw bytes, so that a line that isn't valid UTF-8
+            // doesn't lose our place in the stream
+            let mut line = Vec::new();
+            match self.inner.read_until(b'\n', &mut line) {
+                Ok(0)  => break,
+                Ok(_)  => {}
+                Err(e) => return Some(Err(e)),
+            }
+            if in_body && starts_document(&String::from_utf8_lossy(&line), &self.keyword) {
+                self.pending = line;
+                return Some(utf8(text));
+            }
+            in_body |= !line.starts_with(b"@");
+            text.extend_from_slice(&line);
+        }
+        if text.is_empty() { None } else { Some(utf8(text)) }
+    }
+}
+
+// The text of a finished descriptor, which the parsers need as UTF-8.
+fn utf8(text: Vec<u8>) -> io::Result<String> {
+    String::from_utf8(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
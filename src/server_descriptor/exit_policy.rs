@@ -5,7 +5,7 @@
 
 use std::str;
 use std::net::{Ipv4Addr, Ipv6Addr};
-use nom::hex_digit;
+use nom::{hex_digit, space};
 use nom::IResult;
 
 use grammar::*;
@@ -87,8 +87,8 @@ pub enum AddrSpec {
 named!(addr_spec <AddrSpec>,
     alt!(
         map!(tag!("*"), |_| AddrSpec::Wildcard) |
-        map!(ipv4_spec, |x| AddrSpec::Ipv4(x))  |
-        map!(ipv6_spec, |x| AddrSpec::Ipv6(x))
+        map!(ipv4_spec, AddrSpec::Ipv4)  |
+        map!(ipv6_spec, AddrSpec::Ipv6)
     )
 );
 
@@ -109,7 +109,7 @@ named!(ipv4_spec <Ipv4Spec>,
     alt!(ipv4_spec_cidr | ipv4_spec_addr)
 );
 named!(ipv4_spec_addr <Ipv4Spec>,
-    map!(ipv4_addr, |x| Ipv4Spec::Addr(x) )
+    map!(ipv4_addr, Ipv4Spec::Addr)
 );
 // TODO: ipv4_spec_mask
 named!(ipv4_spec_cidr <Ipv4Spec>,
@@ -117,7 +117,7 @@ named!(ipv4_spec_cidr <Ipv4Spec>,
         addr: ipv4_addr ~
         tag!("/") ~
         bits: ipv4_numbits ,
-        || { Ipv4Spec::CIDR{ addr: addr, prefix: bits } }
+        || { Ipv4Spec::CIDR{ addr, prefix: bits } }
     )
 );
 named!(ipv4_numbits <u8>,
@@ -139,14 +139,14 @@ named!(ipv6_spec <Ipv6Spec>,
     alt!(ipv6_spec_cidr | ipv6_spec_addr)
 );
 named!(ipv6_spec_addr <Ipv6Spec>,
-    map!(ipv6_addr, |x| Ipv6Spec::Addr(x) )
+    map!(ipv6_addr, Ipv6Spec::Addr)
 );
 named!(ipv6_spec_cidr <Ipv6Spec>,
     chain!(
         addr: ipv6_addr ~
         tag!("/") ~
         bits: ipv6_numbits ,
-        || { Ipv6Spec::CIDR{ addr: addr, prefix: bits } }
+        || { Ipv6Spec::CIDR{ addr, prefix: bits } }
     )
 );
 
@@ -212,7 +212,43 @@ named!(port_spec_range <PortSpec>,
         end: u16_digit ,
         || { PortSpec::Range(start..end) } )
 );
-named!(port_spec_port <PortSpec>, map!(u16_digit, |d| PortSpec::Port(d)) );
+named!(port_spec_port <PortSpec>, map!(u16_digit, PortSpec::Port) );
+
+//-----------------------------------------------------------------------------------------------
+
+/// A condensed exit policy, as found in network status documents and microdescriptors.
+///
+/// Rather than a full list of exit patterns, a summary is only a list of ports (and port ranges)
+/// to which most addresses are accepted, or to which most addresses are rejected.
+#[derive(Debug, PartialEq)]
+pub struct PolicySummary {
+    /// Whether the listed ports are accepted or rejected.
+    pub rule: Rule,
+    /// The ports to which the rule applies, in ascending order.
+    pub ports: Vec<PortSpec>,
+}
+
+#[doc(hidden)]
+pub fn parse_policy_summary(i: &[u8]) -> IResult<&[u8], PolicySummary> {
+    policy_summary(i)
+}
+
+// "accept" / "reject" SP PortList
+//
+//    PortList = PortOrRange
+//    PortList = PortList "," PortOrRange
+//    PortOrRange = INT "-" INT / INT
+named!(policy_summary <PolicySummary>,
+    chain!(
+        rule:  alt!(
+                   map!(tag!("accept"), |_| Rule::Accept) |
+                   map!(tag!("reject"), |_| Rule::Reject)
+               ) ~
+               space ~
+        ports: separated_nonempty_list!(tag!(","), port_spec) ,
+        || { PolicySummary{ rule, ports } }
+    )
+);
 
 //-----------------------------------------------------------------------------------------------
 
@@ -264,3 +300,21 @@ fn test_exit_pattern() {
         assert_eq!(res_port, expected_port);
     }
 }
+
+#[test]
+fn test_policy_summary() {
+    let (remaining, summary) = policy_summary(b"accept 20-23,43,53,79-81").unwrap();
+    assert_eq!(remaining, []);
+    assert_eq!(summary, PolicySummary {
+        rule: Rule::Accept,
+        ports: vec![
+            PortSpec::Range(20..23),
+            PortSpec::Port(43),
+            PortSpec::Port(53),
+            PortSpec::Range(79..81),
+        ],
+    });
+
+    let (_, summary) = policy_summary(b"reject 1-65535").unwrap();
+    assert_eq!(summary.rule, Rule::Reject);
+}
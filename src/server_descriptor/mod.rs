@@ -1,19 +1,38 @@
 //! Relay Server Descriptors (`@type server-descriptor 1.0`).
+//!
+//! Sanitized bridge server descriptors (`@type bridge-server-descriptor 1.2`), as published by
+//! CollecTor, are parsed the same way.  In these, the nickname, addresses and fingerprint are
+//! hashed or scrubbed, and the signatures are replaced by `router_digest`/`router_digest_sha256`.
 
+use std::fmt;
 use std::str;
 use std::net::Ipv4Addr;
-use nom::{line_ending, alphanumeric, space};
+use nom::{alphanumeric, space};
 use nom::IResult;
 
 pub mod exit_policy;
 use self::exit_policy::*;
 
 use document::*;
+use cert::{self, Ed25519Cert};
+use digest::{Digest, signed_portion, ed25519_signed_portion};
+#[cfg(feature = "verify")]
+use verify::{self, VerifyError};
 use grammar::*;
 
 /// Common data from a parsed server descriptor.
 #[derive(Default, Debug)]
 pub struct ServerDescriptor<'a> {
+    /// The `@type` annotation the descriptor was prefixed with, which tells relay and bridge
+    /// descriptors apart and records the version of the format.
+    ///
+    /// Descriptors from Tor's own data directory have none.
+    pub type_annotation: Option<TypeAnnotation<'a>>,
+
+    /// Any other annotations the descriptor was prefixed with, such as `@downloaded-at` and
+    /// `@source` in Tor's cached descriptor files, in the order they appear.
+    pub annotations: Vec<Annotation<'a>>,
+
     /// Router nickname.
     pub nickname: &'a str,
 
@@ -70,7 +89,10 @@ pub struct ServerDescriptor<'a> {
     /// A fingerprint (a `HASH_LEN`-byte of asn1 encoded public key, encoded in hex, with a single
     /// space after every 4 characters) for this router's identity key. A descriptor is considered
     /// invalid (and MUST be rejected) if the fingerprint line does not match the public key.
-    pub fingerprint: Option<&'a str>,
+    ///
+    /// This is parsed into a `Digest`, which documents that list relays by an unspaced
+    /// fingerprint, such as exit lists, can be joined on.
+    pub fingerprint: Option<Digest>,
 
     /// The number of seconds that this OR process has been running.
     pub uptime: Option<u64>,
@@ -123,6 +145,18 @@ pub struct ServerDescriptor<'a> {
     /// any new key is published in a subsequent descriptor.
     pub ntor_onion_key: Option<&'a str>,
 
+    /// An RSA signature of the SHA1 digest of the relay's identity key followed by its ed25519
+    /// master identity key, made with the `onion_key`, which proves that the relay owns it.
+    ///
+    /// Required when identity-ed25519 is present; forbidden otherwise.
+    pub onion_key_crosscert: Option<&'a str>,
+
+    /// An ed25519 certificate of the relay's master identity key, made with the ed25519 key
+    /// derived from the `ntor_onion_key`, which proves that the relay owns it.
+    ///
+    /// Required when identity-ed25519 is present; forbidden otherwise.
+    pub ntor_onion_key_crosscert: Option<NtorCrosscert<'a>>,
+
     /// Contains an Ed25519 signature of a SHA256 digest of the entire document, from the first
     /// character up to and including the first space after the "router-sig-ed25519" string,
     /// prefixed with the string "Tor router descriptor signature v1".
@@ -142,6 +176,18 @@ pub struct ServerDescriptor<'a> {
     /// identity key.
     pub router_signature: Option<&'a str>, // TODO: make non-Optional and pre-parse as last Item?
 
+    /// Which bridge distribution method(s) a bridge would prefer to be distributed by, such as
+    /// `https`, `email`, `moat`, `none` or `any`.  (Bridges only.)
+    pub bridge_distribution_request: Option<&'a str>,
+
+    /// The hex-encoded SHA1 digest of the original, unsanitized bridge descriptor.  Sanitized
+    /// bridge descriptors include this in place of a `router_signature`.  (Bridges only.)
+    pub router_digest: Option<&'a str>,
+
+    /// The base64-encoded SHA256 digest of the original, unsanitized bridge descriptor, with
+    /// trailing `=`s removed.  (Bridges only.)
+    pub router_digest_sha256: Option<&'a str>,
+
     /// The rules this OR follows when deciding whether to allow a new stream to a given address.
     pub exit_policy: ExitPolicy,
 
@@ -160,91 +206,348 @@ pub struct ServerDescriptor<'a> {
     /// This is primarily provided for debugging purposes, or if you want to get access to
     /// something strange.
     pub unprocessed_items: Vec<Item<'a>>,
+
+    /// The text of the descriptor, from `router` until the end.
+    text: &'a str,
+
+    /// The keyword of every item, in the order they appeared, for `validate`.
+    keys: Vec<&'a str>,
 }
-// TODO: implement Validate() to check things at end?
 
-// TODO: we can do better than this for communicating error handling.
-pub type ParseError = u32;
+impl<'a> ServerDescriptor<'a> {
+    /// The SHA1 digest of the signed portion of the descriptor, from `router` up to and including
+    /// the `router-signature` line.
+    ///
+    /// This is how the descriptor is referred to by the router status entries of a consensus.
+    /// Sanitized bridge descriptors have no signature, see `router_digest` instead.
+    pub fn digest_sha1(&self) -> Option<Digest> {
+        signed_portion(self.text).map(|signed| Digest::sha1(signed.as_bytes()))
+    }
 
-pub fn parse(input: &str) -> Result<ServerDescriptor, ParseError> {
-    // dont need to have a parse_item function if we understand named macro return type?
-    match server_descriptor_bucket(&input.as_bytes()[..]) {
-        IResult::Done(_i, sd)  => Ok(transmogrify(sd)),
-        IResult::Error(_)      => Err(1),
-        IResult::Incomplete(_) => Err(2),
+    /// The SHA256 digest signed by `router_sig_ed25519`: of the descriptor up to and including the
+    /// space after `router-sig-ed25519`, prefixed with `Tor router descriptor signature v1`.
+    ///
+    /// Descriptors without an ed25519 identity have no such signature, and so no such digest.
+    pub fn digest_sha256(&self) -> Option<Digest> {
+        ed25519_signed_portion(self.text).map(|signed| {
+            Digest::sha256(format!("Tor router descriptor signature v1{}", signed).as_bytes())
+        })
+    }
+
+    /// The SHA1 digest of the DER encoding of the relay's identity key, its `signing_key`, or
+    /// `None` if it is missing or malformed.
+    ///
+    /// The `fingerprint` is this digest in hex, and router status entries refer to the relay by
+    /// it in base64.
+    pub fn identity_digest(&self) -> Option<Digest> {
+        match typed_object(self.signing_key?.as_bytes()) {
+            IResult::Done(_, obj) if obj.keyword == "RSA PUBLIC KEY" => Some(Digest::sha1(&obj.decode()?)),
+            _ => None,
+        }
     }
+
+    /// The certificate in `identity_ed25519`, of the relay's signing key by its master identity
+    /// key, or `None` if it is missing or malformed.
+    pub fn identity_cert(&self) -> Option<Ed25519Cert> {
+        ed25519_cert(self.identity_ed25519?)
+    }
+
+    /// Verify the `router_signature` of the descriptor against its `signing_key`.
+    ///
+    /// Only available with the `verify` feature.
+    #[cfg(feature = "verify")]
+    pub fn verify_router_signature(&self) -> Result<(), VerifyError> {
+        let key = verify::rsa_key(self.signing_key, "signing-key")?;
+        let digest = self.digest_sha1().ok_or(VerifyError::Missing("router-signature"))?;
+        verify::rsa_signature(&key, digest.as_bytes(), self.router_signature, "SIGNATURE", "router-signature")
+    }
+
+    /// Check that the `fingerprint` of the descriptor is that of its `signing_key`.
+    ///
+    /// Only available with the `verify` feature.
+    #[cfg(feature = "verify")]
+    pub fn verify_fingerprint(&self) -> Result<(), VerifyError> {
+        let claimed = self.fingerprint.as_ref().ok_or(VerifyError::Missing("fingerprint"))?;
+        verify::rsa_key(self.signing_key, "signing-key")?;
+        let computed = self.identity_digest().ok_or(VerifyError::Malformed("signing-key"))?;
+        if *claimed == computed {
+            Ok(())
+        } else {
+            Err(VerifyError::FingerprintMismatch { computed: computed.spaced_hex(), claimed: claimed.spaced_hex() })
+        }
+    }
+
+    /// Verify the `identity_ed25519` certificate, that `master_key_ed25519` is the key which
+    /// signed it, and the `router_sig_ed25519` of the descriptor against the certified signing
+    /// key.
+    ///
+    /// The expiration of the certificate is not checked, since that depends on when the
+    /// descriptor is used.  Only available with the `verify` feature.
+    #[cfg(feature = "verify")]
+    pub fn verify_ed25519(&self) -> Result<(), VerifyError> {
+        let (cert, master_key) = self.identity()?;
+
+        let claimed = self.master_key_ed25519.ok_or(VerifyError::Missing("master-key-ed25519"))?;
+        if decode_base64(claimed).as_ref().map(|key| &key[..]) != Some(&master_key[..]) {
+            return Err(VerifyError::KeyMismatch("master-key-ed25519"));
+        }
+        verify::ed25519_cert(&cert, &master_key, "identity-ed25519")?;
+
+        let digest = self.digest_sha256().ok_or(VerifyError::Missing("router-sig-ed25519"))?;
+        verify::ed25519_signature(&cert.certified_key, digest.as_bytes(), self.router_sig_ed25519,
+                                  "router-sig-ed25519")
+    }
+
+    /// Verify the `onion_key_crosscert` and `ntor_onion_key_crosscert` of the descriptor, which
+    /// prove that the relay owns its onion keys, against the ed25519 master identity key from
+    /// `identity_ed25519`.
+    ///
+    /// Only available with the `verify` feature.
+    #[cfg(feature = "verify")]
+    pub fn verify_crosscerts(&self) -> Result<(), VerifyError> {
+        let (_, master_key) = self.identity()?;
+
+        let identity_digest = self.identity_digest().ok_or(VerifyError::Malformed("signing-key"))?;
+        let onion_key = verify::rsa_key(self.onion_key, "onion-key")?;
+        let signed = [identity_digest.as_bytes(), &master_key[..]].concat();
+        verify::rsa_signature(&onion_key, &signed, self.onion_key_crosscert, "CROSSCERT", "onion-key-crosscert")?;
+
+        let crosscert = self.ntor_onion_key_crosscert.ok_or_else(|| self.absent("ntor-onion-key-crosscert"))?;
+        let cert = match crosscert.cert() {
+            Some(cert) if cert.cert_type == cert::NTOR_ONION_KEY => cert,
+            _ => return Err(VerifyError::Malformed("ntor-onion-key-crosscert")),
+        };
+        if cert.certified_key != master_key {
+            return Err(VerifyError::KeyMismatch("ntor-onion-key-crosscert"));
+        }
+        let ntor_key = self.ntor_onion_key.ok_or(VerifyError::Missing("ntor-onion-key"))?;
+        let ed25519_key = decode_base64(ntor_key)
+            .and_then(|key| verify::ed25519_from_curve25519(&key, crosscert.sign))
+            .ok_or(VerifyError::Malformed("ntor-onion-key"))?;
+        verify::ed25519_cert(&cert, &ed25519_key, "ntor-onion-key-crosscert")
+    }
+
+    // Why an item is not in its field: either it's missing, or it is in `unprocessed_items`
+    // because it was malformed, such as an `ntor-onion-key-crosscert` with a sign other than 0 or 1.
+    #[cfg(feature = "verify")]
+    fn absent(&self, keyword: &'static str) -> VerifyError {
+        if self.unprocessed_items.iter().any(|item| item.key == keyword) {
+            VerifyError::Malformed(keyword)
+        } else {
+            VerifyError::Missing(keyword)
+        }
+    }
+
+    // The `identity_ed25519` certificate, along with the master identity key it was signed with.
+    #[cfg(feature = "verify")]
+    fn identity(&self) -> Result<(Ed25519Cert, [u8; 32]), VerifyError> {
+        self.identity_ed25519.ok_or(VerifyError::Missing("identity-ed25519"))?;
+        let cert = match self.identity_cert() {
+            Some(cert) if cert.cert_type == cert::SIGNING_KEY => cert,
+            _ => return Err(VerifyError::Malformed("identity-ed25519")),
+        };
+        let master_key = cert.signing_key().ok_or(VerifyError::Malformed("identity-ed25519"))?;
+        Ok((cert, master_key))
+    }
+
+    /// Check that the items of the descriptor appear where, and as many times as, the dir-spec
+    /// requires, returning every rule that is broken.
+    ///
+    /// Sanitized bridge descriptors are not expected to have the `onion-key`, `signing-key` or
+    /// `router-signature` items, since CollecTor removes them.
+    pub fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        let sanitized = self.type_annotation.is_some_and(|ta| ta.name == "bridge-server-descriptor");
+        let count = |key| self.keys.iter().filter(|&&k| k == key).count();
+        let position = |key| self.keys.iter().position(|&k| k == key);
+
+        // [At start, exactly once.]
+        match count("router") {
+            0 => violations.push(Violation::Missing("router")),
+            1 => {}
+            n => violations.push(Violation::Repeated("router", n)),
+        }
+        if position("router").is_some_and(|i| i != 0) {
+            violations.push(Violation::Misplaced("router"));
+        }
+
+        // [Exactly once.]
+        let mut once = vec!["bandwidth", "published"];
+        if !sanitized {
+            once.extend(["onion-key", "signing-key", "router-signature"]);
+        }
+        for key in once {
+            match count(key) {
+                0 => violations.push(Violation::Missing(key)),
+                1 => {}
+                n => violations.push(Violation::Repeated(key, n)),
+            }
+        }
+
+        // [At most once.]
+        let at_most_once = [
+            "platform", "proto", "protocols", "fingerprint", "hibernating", "uptime",
+            "extra-info-digest", "contact", "family", "read-history", "write-history", "eventdns",
+            "caches-extra-info", "hidden-service-dir", "allow-single-hop-exits",
+            "tunnelled-dir-server", "master-key-ed25519", "ntor-onion-key", "ipv6-policy",
+            "bridge-distribution-request",
+        ];
+        for key in at_most_once {
+            match count(key) {
+                0 | 1 => {}
+                n => violations.push(Violation::Repeated(key, n)),
+            }
+        }
+
+        // [At end, exactly once.]
+        if position("router-signature").is_some_and(|i| i != self.keys.len() - 1) {
+            violations.push(Violation::Misplaced("router-signature"));
+        }
+
+        // [At most once, in second position, following the router line.]
+        match count("identity-ed25519") {
+            0 | 1 => {}
+            n => violations.push(Violation::Repeated("identity-ed25519", n)),
+        }
+        if position("identity-ed25519").is_some_and(|i| i != 1) {
+            violations.push(Violation::Misplaced("identity-ed25519"));
+        }
+
+        // [Exactly once, if there is an identity-ed25519; forbidden otherwise.]
+        let identity = count("identity-ed25519") > 0;
+        for key in ["onion-key-crosscert", "ntor-onion-key-crosscert", "router-sig-ed25519"] {
+            match (identity, count(key)) {
+                (false, 0) | (true, 1) => {}
+                (true, 0)  => violations.push(Violation::Missing(key)),
+                (false, _) => violations.push(Violation::Unexpected(key)),
+                (true, n)  => violations.push(Violation::Repeated(key, n)),
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+/// The `ntor-onion-key-crosscert` of a server descriptor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NtorCrosscert<'a> {
+    /// The sign bit of the ed25519 key derived from the `ntor_onion_key`, which can't be
+    /// recovered from the curve25519 key itself.
+    pub sign: u8,
+    /// The `ED25519 CERT` object.
+    pub cert: &'a str,
 }
 
-pub fn parse_all(input: &str) -> Vec<ServerDescriptor> {
-    extract_all_item_buckets(input).into_iter().map(transmogrify).collect()
+impl<'a> NtorCrosscert<'a> {
+    /// The certificate, or `None` if it is malformed.
+    pub fn cert(&self) -> Option<Ed25519Cert> {
+        ed25519_cert(self.cert)
+    }
+}
+
+// Decode the certificate in an `ED25519 CERT` object.
+fn ed25519_cert(object: &str) -> Option<Ed25519Cert> {
+    match typed_object(object.as_bytes()) {
+        IResult::Done(_, obj) if obj.keyword == "ED25519 CERT" => cert::parse(&obj.decode()?),
+        _ => None,
+    }
+}
+
+/// A way in which a server descriptor breaks the rules of the dir-spec for where, and how many
+/// times, its items may appear.  Each names the keyword of the item concerned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Violation {
+    /// A required item does not appear.
+    Missing(&'static str),
+    /// An item that may only appear once appears this many times.
+    Repeated(&'static str, usize),
+    /// An item is not in the position it must be in.
+    Misplaced(&'static str),
+    /// An item appears that must not, such as `router-sig-ed25519` without `identity-ed25519`.
+    Unexpected(&'static str),
 }
 
-fn extract_all_item_buckets(input: &str) -> Vec<Vec<Item>> {
-    match server_descriptor_bucket_aggregator(&input.as_bytes()[..]) {
-        IResult::Done(_i, sda) => sda,
-        _ => Vec::new()
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::Missing(key)         => write!(f, "missing {:?}", key),
+            Violation::Repeated(key, count) => write!(f, "{:?} appears {} times", key, count),
+            Violation::Misplaced(key)       => write!(f, "{:?} is out of place", key),
+            Violation::Unexpected(key)      => write!(f, "unexpected {:?}", key),
+        }
     }
 }
 
+pub use ParseError;
+use error::{finish, relocate, Syntax};
+
+pub fn parse(input: &str) -> Result<ServerDescriptor<'_>, ParseError> {
+    // dont need to have a parse_item function if we understand named macro return type?
+    let (ta, annotations, (text, sd)) = finish(input, server_descriptor_bucket(input.as_bytes()), Syntax::KeywordLines)?;
+    Ok(transmogrify(ta, annotations, text, sd))
+}
+
+/// Parse all of the server descriptors in `input`, skipping any which are malformed.
+pub fn parse_all(input: &str) -> Vec<ServerDescriptor<'_>> {
+    parse_each(input).filter_map(Result::ok).collect()
+}
+
+/// Parse each of the server descriptors in `input`, such as a CollecTor file, in turn.
+///
+/// A malformed descriptor doesn't stop parsing: its error is returned in its place, and parsing
+/// resumes at the next `@type` annotation or `router` line.  The position of each error is
+/// relative to the whole of `input`.
+pub fn parse_each(input: &str) -> impl Iterator<Item = Result<ServerDescriptor<'_>, ParseError>> {
+    documents(input, "router").map(move |(start, text)| parse(text).map_err(|e| relocate(e, input, start)))
+}
+
 /// Transform a "bucket of items" returns from the parser into a ServiceDescriptor struct.
-fn transmogrify(item_bucket: Vec<Item>) -> ServerDescriptor { // TODO: make this a result
-    let mut sd: ServerDescriptor = Default::default();
+fn transmogrify<'a>(type_annotation: Option<TypeAnnotation<'a>>,
+                    annotations: Vec<Annotation<'a>>,
+                    text: &'a str,
+                    item_bucket: Vec<Item<'a>>) -> ServerDescriptor<'a> { // TODO: make this a result
+    let mut sd = ServerDescriptor { type_annotation, annotations, text, ..Default::default() };
 
     for item in item_bucket {
-        // common pattern for an Item with a KeywordLine consisting of one REQUIRED arg that is
-        // simply treated as a blob of text, with no additional processing required, just store it
-        // in `$field`.
-        macro_rules! singleton_arg { (.$field:ident) => {{
-            if let (Some(args), 0) = (item.args, item.objs.len()) {
-                sd.$field = Some(args);
-            } else {
-                sd.unprocessed_items.push(item);
-            }
-        }}}
-
-        // common pattern for an Item that contains exactly one object (& no args), which will be
-        // simply treated as a blob of text, with no additional processing required, just store it
-        // in `$field`.
-        macro_rules! first_obj { (.$field:ident) => {{
-            if (None, 1) == (item.args, item.objs.len()) {
-                sd.$field = Some(item.objs[0]); //safe because of above len() check
-            } else {
-                sd.unprocessed_items.push(item);
+        sd.keys.push(item.key);
+        match item.key {
+            "platform"             => singleton_arg!(item => sd.platform),
+            "identity-ed25519"     => first_obj!(item => sd.identity_ed25519),
+            "master-key-ed25519"   => singleton_arg!(item => sd.master_key_ed25519),
+            "protocols"            => singleton_arg!(item => sd.protocols),
+            "fingerprint" => {
+                if let (Some(fingerprint), 0) = (item.args.and_then(Digest::from_fingerprint), item.objs.len()) {
+                    sd.fingerprint = Some(fingerprint);
+                } else {
+                    sd.unprocessed_items.push(item);
+                }
             }
-        }}}
-
-        // common pattern for an Item where the KeywordLine args will parsed with an additional
-        // Nom parser.  Takes the identifier of the parser,  and a closure which will function
-        // as the results handler for the return value of a successful parse.
-        //
-        // If the parser fails for any reason (error, incompete data), the Item is merely added
-        // to the unprocessed_items list.
-        macro_rules! use_parser { ($parser:ident, $results_handler:expr) => {{
-            if let Some(args) = item.args {
-                if let IResult::Done(_, res) = $parser(args.as_bytes()) {
-                    $results_handler(res);
-                    continue;
+            "published"            => singleton_arg!(item => sd.published),
+            "extra-info-digest"    => singleton_arg!(item => sd.extra_info_digest),
+            "onion-key"            => first_obj!(item => sd.onion_key),
+            "signing-key"          => first_obj!(item => sd.signing_key),
+            "contact"              => singleton_arg!(item => sd.contact),
+            "ntor-onion-key"       => singleton_arg!(item => sd.ntor_onion_key),
+            "onion-key-crosscert"  => first_obj!(item => sd.onion_key_crosscert),
+
+            "ntor-onion-key-crosscert" => {
+                match (item.args, item.objs.len()) {
+                    (Some("0"), 1) => sd.ntor_onion_key_crosscert = Some(NtorCrosscert{ sign: 0, cert: item.objs[0] }),
+                    (Some("1"), 1) => sd.ntor_onion_key_crosscert = Some(NtorCrosscert{ sign: 1, cert: item.objs[0] }),
+                    _ => sd.unprocessed_items.push(item),
                 }
             }
-            sd.unprocessed_items.push(item);
-        }}}
+            "router-sig-ed25519"   => singleton_arg!(item => sd.router_sig_ed25519),
+            "router-signature"     => first_obj!(item => sd.router_signature),
+            "bridge-distribution-request" => singleton_arg!(item => sd.bridge_distribution_request),
+            "router-digest"        => singleton_arg!(item => sd.router_digest),
+            "router-digest-sha256" => singleton_arg!(item => sd.router_digest_sha256),
 
-        match item.key {
-            "platform"             => singleton_arg!(.platform),
-            "identity-ed25519"     => first_obj!(.identity_ed25519),
-            "master-key-ed25519"   => singleton_arg!(.master_key_ed25519),
-            "protocols"            => singleton_arg!(.protocols),
-            "fingerprint"          => singleton_arg!(.fingerprint),
-            "published"            => singleton_arg!(.published),
-            "extra-info-digest"    => singleton_arg!(.extra_info_digest),
-            "onion-key"            => first_obj!(.onion_key),
-            "signing-key"          => first_obj!(.signing_key),
-            "contact"              => singleton_arg!(.contact),
-            "ntor-onion-key"       => singleton_arg!(.ntor_onion_key),
-            "router-sig-ed25519"   => singleton_arg!(.router_sig_ed25519),
-            "router-signature"     => first_obj!(.router_signature),
-
-            "router" => use_parser!(router, |r| {
+            "router" => use_parser!(item => sd, router, |r| {
                 let (nickname, address, or_port, socks_port, dir_port) = r;
                 sd.nickname   = nickname;
                 sd.address    = Some(address);
@@ -253,7 +556,7 @@ fn transmogrify(item_bucket: Vec<Item>) -> ServerDescriptor { // TODO: make this
                 sd.dir_port   = dir_port;
             }),
 
-            "bandwidth" => use_parser!(bandwidth, |r| {
+            "bandwidth" => use_parser!(item => sd, bandwidth, |r| {
                 let (avg, bur, obs)   = r;
                 sd.bandwidth_avg      = avg;
                 sd.bandwidth_burst    = bur;
@@ -261,7 +564,7 @@ fn transmogrify(item_bucket: Vec<Item>) -> ServerDescriptor { // TODO: make this
             }),
 
             "uptime" => {
-                use_parser!(uptime, |r| sd.uptime = Some(r) )
+                use_parser!(item => sd, uptime, |r| sd.uptime = Some(r) )
             }
 
             "hidden-service-dir" => {
@@ -275,8 +578,8 @@ fn transmogrify(item_bucket: Vec<Item>) -> ServerDescriptor { // TODO: make this
                     _ => unreachable!(),
                 };
 
-                use_parser!(parse_exit_pattern, |(a,p)| {
-                    sd.exit_policy.push( ExitPattern{ rule: rule, addr: a, port: p } );
+                use_parser!(item => sd, parse_exit_pattern, |(a,p)| {
+                    sd.exit_policy.push( ExitPattern{ rule, addr: a, port: p } );
                 })
             }
 
@@ -290,16 +593,19 @@ fn transmogrify(item_bucket: Vec<Item>) -> ServerDescriptor { // TODO: make this
 
 
 
-named!(server_descriptor_bucket_aggregator < Vec<Vec<Item>> >, many0!(server_descriptor_bucket));
-named!(server_descriptor_bucket < Vec<Item> >,
+/// The annotations, text and items of a server descriptor, as returned from the parser.
+type Bucket<'a> = (Option<TypeAnnotation<'a>>, Vec<Annotation<'a>>, (&'a str, Vec<Item<'a>>));
+
+named!(server_descriptor_bucket <Bucket<'a>>,
     chain!(
-        tag!("@type server-descriptor 1.0") ~ line_ending ~
-        items: many1!(item) ,
-        || { items }
+        ta:          opt!(complete!(map_opt!(type_annotation, accepted_as(DescriptorType::ServerDescriptor)))) ~
+        annotations: many0!(annotation) ~
+                     peek!(tag!("router ")) ~
+        items:       items_with_text ,
+        || { (ta, annotations, items) }
     )
 );
 
-
 // "router" nickname address ORPort SOCKSPort DirPort NL
 //
 //   [At start, exactly once.]
@@ -0,0 +1,226 @@
+//! Torperf and OnionPerf measurement results (`@type torperf 1.0`).
+//!
+//! Each line of a `.tpf` file is the result of a single download made through the Tor network,
+//! as a list of space-separated `KEY=VALUE` pairs.  Most of the values are the times at which
+//! the download reached a certain stage, in seconds since the epoch with fractional digits.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use nom::{line_ending, space};
+
+use document::*;
+use digest::Digest;
+use grammar::*;
+use ParseError;
+use error::{finish, relocate, Syntax};
+
+/// A single measurement result.
+#[derive(Default, Debug)]
+pub struct TorperfResult<'a> {
+    /// The `@type` annotation of the file the result was read from, which records the version of
+    /// the format.
+    pub type_annotation: Option<TypeAnnotation<'a>>,
+
+    /// The configured name of the data source, such as `moria`.
+    pub source: Option<&'a str>,
+
+    /// The configured size of the downloaded file, in bytes.
+    pub filesize: Option<u64>,
+
+    /// The time the measurement was started.
+    pub start: Option<SystemTime>,
+
+    /// The time the socket was created.
+    pub socket: Option<SystemTime>,
+
+    /// The time the socket was connected.
+    pub connect: Option<SystemTime>,
+
+    /// The time SOCKS negotiation completed.
+    pub negotiate: Option<SystemTime>,
+
+    /// The time the SOCKS request was sent.
+    pub request: Option<SystemTime>,
+
+    /// The time the SOCKS response was received.
+    pub response: Option<SystemTime>,
+
+    /// The time the HTTP request was written.
+    pub datarequest: Option<SystemTime>,
+
+    /// The time the first response byte was read.
+    pub dataresponse: Option<SystemTime>,
+
+    /// The time the download completed.
+    pub datacomplete: Option<SystemTime>,
+
+    /// The times at which each decile of the expected bytes was read, from the `DATAPERC*` keys,
+    /// as `(percent, time)` in ascending order of percent.
+    pub dataperc: Vec<(u8, SystemTime)>,
+
+    /// The number of bytes written.
+    pub writebytes: Option<u64>,
+
+    /// The number of bytes read.
+    pub readbytes: Option<u64>,
+
+    /// Whether the measurement timed out before the download completed.
+    pub didtimeout: Option<bool>,
+
+    /// The time the circuit used for the measurement was launched.
+    pub launch: Option<SystemTime>,
+
+    /// The time the circuit was attached to the stream of the measurement.
+    pub used_at: Option<SystemTime>,
+
+    /// The fingerprints of the relays of the circuit used for the measurement, which are written
+    /// in hex with no spaces, each prefixed with a `$`.
+    pub path: Vec<Digest>,
+
+    /// The time each hop of the circuit was built, relative to `launch`.
+    pub buildtimes: Vec<Duration>,
+
+    /// `KEY=VALUE` pairs we have successfully parsed, but have not been processed into structured
+    /// data, in the order they appear.
+    ///
+    /// This is the equivalent of `ServerDescriptor::unprocessed_items`.
+    pub unprocessed_keys: Vec<(&'a str, &'a str)>,
+}
+
+pub fn parse(input: &str) -> Result<Vec<TorperfResult<'_>>, ParseError> {
+    let (annotation, lines) = finish(input, torperf_bucket(input.as_bytes()), Syntax::KeyValues)?;
+    Ok(lines.into_iter().map(|pairs| transmogrify(annotation, pairs)).collect())
+}
+
+/// Parse the results of all of the torperf files in `input`, skipping any files which are
+/// malformed.
+pub fn parse_all(input: &str) -> Vec<TorperfResult<'_>> {
+    parse_each(input).filter_map(Result::ok).flatten().collect()
+}
+
+/// Parse the results of each of the torperf files in `input` in turn, resuming at the next one
+/// after any which are malformed.  Each of them needs to be preceded by its `@type` annotation.
+///
+/// See `server_descriptor::parse_each`.
+pub fn parse_each(input: &str) -> impl Iterator<Item = Result<Vec<TorperfResult<'_>>, ParseError>> {
+    annotated_documents(input).map(move |(start, text)| parse(text).map_err(|e| relocate(e, input, start)))
+}
+
+/// Transform the pairs of a single result line into a TorperfResult struct.
+///
+/// Pairs with values that can't be interpreted end up in `unprocessed_keys`.
+fn transmogrify<'a>(annotation: TypeAnnotation<'a>, pairs: Vec<(&'a str, &'a str)>) -> TorperfResult<'a> {
+    let mut tr = TorperfResult { type_annotation: Some(annotation), ..Default::default() };
+
+    for (key, value) in pairs {
+        let processed = match key {
+            "SOURCE"       => { tr.source = Some(value); true }
+            "FILESIZE"     => set(&mut tr.filesize, value.parse().ok()),
+            "START"        => set(&mut tr.start, timestamp(value)),
+            "SOCKET"       => set(&mut tr.socket, timestamp(value)),
+            "CONNECT"      => set(&mut tr.connect, timestamp(value)),
+            "NEGOTIATE"    => set(&mut tr.negotiate, timestamp(value)),
+            "REQUEST"      => set(&mut tr.request, timestamp(value)),
+            "RESPONSE"     => set(&mut tr.response, timestamp(value)),
+            "DATAREQUEST"  => set(&mut tr.datarequest, timestamp(value)),
+            "DATARESPONSE" => set(&mut tr.dataresponse, timestamp(value)),
+            "DATACOMPLETE" => set(&mut tr.datacomplete, timestamp(value)),
+            "WRITEBYTES"   => set(&mut tr.writebytes, value.parse().ok()),
+            "READBYTES"    => set(&mut tr.readbytes, value.parse().ok()),
+            "LAUNCH"       => set(&mut tr.launch, timestamp(value)),
+            "USED_AT"      => set(&mut tr.used_at, timestamp(value)),
+
+            "DIDTIMEOUT" => match value {
+                "0" => { tr.didtimeout = Some(false); true }
+                "1" => { tr.didtimeout = Some(true); true }
+                _   => false,
+            },
+
+            "PATH" => match value.split(',').map(hop).collect() {
+                Some(path) => { tr.path = path; true }
+                None => false,
+            },
+
+            "BUILDTIMES" => match value.split(',').map(seconds).collect() {
+                Some(buildtimes) => { tr.buildtimes = buildtimes; true }
+                None => false,
+            },
+
+            _ if key.starts_with("DATAPERC") => {
+                match (key["DATAPERC".len()..].parse(), timestamp(value)) {
+                    (Ok(percent), Some(time)) => { tr.dataperc.push((percent, time)); true }
+                    _ => false,
+                }
+            }
+
+            _ => false,
+        };
+        if !processed {
+            tr.unprocessed_keys.push((key, value));
+        }
+    }
+    tr.dataperc.sort_by_key(|&(percent, _)| percent);
+    tr
+}
+
+// store `value` in `field` if it could be interpreted, returning whether it was.
+fn set<T>(field: &mut Option<T>, value: Option<T>) -> bool {
+    let processed = value.is_some();
+    if processed {
+        *field = value;
+    }
+    processed
+}
+
+// A relay in a `PATH`, its fingerprint prefixed with a `$`.
+fn hop(value: &str) -> Option<Digest> {
+    Digest::from_fingerprint(value.trim_start_matches('$'))
+}
+
+// A number of seconds with an optional fractional part, such as `1470456001.55` or `0.44`.
+fn seconds(value: &str) -> Option<Duration> {
+    let (secs, frac) = match value.find('.') {
+        Some(pos) => (&value[..pos], &value[pos + 1..]),
+        None      => (value, ""),
+    };
+    if !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    // anything beyond nanosecond precision is truncated
+    let nanos = format!("{:0<9.9}", frac).parse().ok()?;
+    Some(Duration::new(secs.parse().ok()?, nanos))
+}
+
+// A point in time, as a number of seconds since the epoch.
+fn timestamp(value: &str) -> Option<SystemTime> {
+    seconds(value).map(|d| UNIX_EPOCH + d)
+}
+
+
+/// The annotation and the pairs of each result line of a torperf file, as returned from the parser.
+type Bucket<'a> = (TypeAnnotation<'a>, Vec<Vec<(&'a str, &'a str)>>);
+
+named!(torperf_bucket <Bucket<'a>>,
+    chain!(
+        annotation: map_opt!(type_annotation, accepted_as(DescriptorType::Torperf)) ~
+        lines:      many1!(result_line) ,
+        || { (annotation, lines) }
+    )
+);
+// Result lines are made of space-separated `KEY=VALUE` pairs, sorted by key.
+named!(result_line < Vec<(&'a str, &'a str)> >,
+    chain!(
+        pairs: separated_nonempty_list!(space, key_value) ~
+               line_ending ,
+        || { pairs }
+    )
+);
+
+#[test]
+fn test_seconds() {
+    assert_eq!(seconds("1470456001.55"),  Some(Duration::new(1470456001, 550_000_000)));
+    assert_eq!(seconds("0.44"),           Some(Duration::new(0, 440_000_000)));
+    assert_eq!(seconds("1500"),           Some(Duration::new(1500, 0)));
+    assert_eq!(seconds("0.940000057220"), Some(Duration::new(0, 940_000_057)));
+    assert_eq!(seconds("1.2.3"),          None);
+    assert_eq!(seconds("-1"),             None);
+}
@@ -0,0 +1,115 @@
+//! Verification of the signatures and keys in descriptors.
+//!
+//! Only available with the `verify` feature.  Parsing never checks signatures, so a descriptor
+//! which has been parsed successfully may still be forged or corrupted.
+
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+
+use nom::IResult;
+use rsa::{Pkcs1v15Sign, RsaPublicKey};
+use rsa::pkcs1::DecodeRsaPublicKey;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use curve25519_dalek::montgomery::MontgomeryPoint;
+
+use cert::{self, Ed25519Cert};
+use document::{decode_base64, typed_object};
+
+/// The reason a descriptor failed verification.
+///
+/// Each variant names the keyword of the item at fault, such as `router-signature`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyError {
+    /// The item is required for verification, but missing.
+    Missing(&'static str),
+    /// The key or signature in the item can't be decoded.
+    Malformed(&'static str),
+    /// The signature in the item does not match.
+    BadSignature(&'static str),
+    /// The key in the item is not the one that signed the descriptor or its certificates.
+    KeyMismatch(&'static str),
+    /// The `fingerprint` is not that of the identity key.
+    FingerprintMismatch { computed: String, claimed: String },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VerifyError::Missing(keyword)      => write!(f, "missing {}", keyword),
+            VerifyError::Malformed(keyword)    => write!(f, "malformed {}", keyword),
+            VerifyError::BadSignature(keyword) => write!(f, "{} does not match", keyword),
+            VerifyError::KeyMismatch(keyword)  => write!(f, "{} is not the signing key", keyword),
+            VerifyError::FingerprintMismatch { ref computed, ref claimed } => {
+                write!(f, "fingerprint {} does not match the identity key, whose fingerprint is {}",
+                       claimed, computed)
+            }
+        }
+    }
+}
+
+impl error::Error for VerifyError {}
+
+/// Decode the RSA public key in an `RSA PUBLIC KEY` object, as found in the item `keyword`.
+pub fn rsa_key(object: Option<&str>, keyword: &'static str) -> Result<RsaPublicKey, VerifyError> {
+    let der = decode(object, "RSA PUBLIC KEY", keyword)?;
+    RsaPublicKey::from_pkcs1_der(&der).map_err(|_| VerifyError::Malformed(keyword))
+}
+
+/// Verify the RSA signature in an object of type `object_type`, such as `SIGNATURE`, found in
+/// the item `keyword`, over `signed`, which is usually a digest.
+///
+/// Tor pads the signed data as in PKCS#1 v1.5, but without the `DigestInfo` prefix identifying
+/// the hash algorithm.
+pub fn rsa_signature(key: &RsaPublicKey, signed: &[u8], object: Option<&str>, object_type: &str,
+                     keyword: &'static str) -> Result<(), VerifyError> {
+    let signature = decode(object, object_type, keyword)?;
+    key.verify(Pkcs1v15Sign::new_unprefixed(), signed, &signature)
+       .map_err(|_| VerifyError::BadSignature(keyword))
+}
+
+/// Verify that `cert`, found in the item `keyword`, was signed by the ed25519 key `key`.
+///
+/// Certificates with an extension we don't understand, but which affects their validation, are
+/// rejected as malformed.
+pub fn ed25519_cert(cert: &Ed25519Cert, key: &[u8; 32], keyword: &'static str) -> Result<(), VerifyError> {
+    let unknown = cert.extensions.iter().any(|ext| {
+        ext.flags & cert::AFFECTS_VALIDATION != 0 && ext.ext_type != cert::SIGNED_WITH_ED25519_KEY
+    });
+    if cert.version != 1 || unknown {
+        return Err(VerifyError::Malformed(keyword));
+    }
+    let key = VerifyingKey::from_bytes(key).map_err(|_| VerifyError::Malformed(keyword))?;
+    key.verify(cert.signed(), &Signature::from_bytes(&cert.signature))
+       .map_err(|_| VerifyError::BadSignature(keyword))
+}
+
+/// Verify the base64-encoded ed25519 `signature`, found in the item `keyword`, of `message` by
+/// the ed25519 key `key`.
+pub fn ed25519_signature(key: &[u8; 32], message: &[u8], signature: Option<&str>, keyword: &'static str)
+                         -> Result<(), VerifyError> {
+    let signature = signature.ok_or(VerifyError::Missing(keyword))?;
+    let signature = decode_base64(signature)
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .ok_or(VerifyError::Malformed(keyword))?;
+    let key = VerifyingKey::from_bytes(key).map_err(|_| VerifyError::Malformed(keyword))?;
+    key.verify(message, &signature).map_err(|_| VerifyError::BadSignature(keyword))
+}
+
+/// The ed25519 public key corresponding to the curve25519 public key `key`, with the sign bit
+/// `sign`, or `None` if `key` is malformed.
+///
+/// Tor derives an ed25519 key from the curve25519 `ntor-onion-key` to sign its crosscert with.
+pub fn ed25519_from_curve25519(key: &[u8], sign: u8) -> Option<[u8; 32]> {
+    let key = <[u8; 32]>::try_from(key).ok()?;
+    MontgomeryPoint(key).to_edwards(sign).map(|point| point.compress().to_bytes())
+}
+
+// Decode an object of type `object_type` from the item `keyword`.
+fn decode(object: Option<&str>, object_type: &str, keyword: &'static str) -> Result<Vec<u8>, VerifyError> {
+    let object = object.ok_or(VerifyError::Missing(keyword))?;
+    match typed_object(object.as_bytes()) {
+        IResult::Done(_, obj) if obj.keyword == object_type => obj.decode().ok_or(VerifyError::Malformed(keyword)),
+        _ => Err(VerifyError::Malformed(keyword)),
+    }
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "archive")]
+
+extern crate bzip2;
+extern crate flate2;
+extern crate tar;
+extern crate tordesc;
+extern crate xz2;
+
+use std::io::{Cursor, Write};
+use std::path::Path;
+use tordesc::Descriptor;
+use tordesc::archive::Archive;
+
+static SAMPLE: &str = r#"@type tordnsel 1.0
+Downloaded 2016-08-06 03:02:01
+ExitNode 0011BD2485AD45D984EC4159C88FC066E5E3300E
+Published 2016-08-05 20:06:12
+LastStatus 2016-08-06 02:02:56
+ExitAddress 162.247.72.201 2016-08-06 02:17:06
+"#;
+
+// A tar archive of two exit lists, with a directory entry in between.
+fn tarball() -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(SAMPLE.len() as u64);
+    header.set_mode(0o644);
+    builder.append_data(&mut header.clone(), "exit-list-2016-08/2016-08-06-03-02-01", SAMPLE.as_bytes()).unwrap();
+
+    let mut dir = tar::Header::new_gnu();
+    dir.set_entry_type(tar::EntryType::Directory);
+    dir.set_size(0);
+    dir.set_mode(0o755);
+    builder.append_data(&mut dir, "exit-list-2016-08/06/", &[][..]).unwrap();
+
+    builder.append_data(&mut header, "exit-list-2016-08/06/2016-08-06-04-02-01", SAMPLE.as_bytes()).unwrap();
+    builder.into_inner().unwrap()
+}
+
+fn check(mut archive: Archive) {
+    let members: Vec<_> = archive.members().unwrap().map(Result::unwrap).collect();
+    assert_eq!(members.len(), 2);
+    assert_eq!(members[1].path, Path::new("exit-list-2016-08/06/2016-08-06-04-02-01"));
+    for member in &members {
+        let descriptors: Vec<_> = member.parse().collect();
+        match &descriptors[..] {
+            [Ok(Descriptor::ExitList(el))] => assert_eq!(el.exit_nodes.len(), 1),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn read_tar() {
+    check(Archive::new(Cursor::new(tarball())).unwrap());
+}
+
+#[test]
+fn read_tar_gz() {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&tarball()).unwrap();
+    check(Archive::new(Cursor::new(encoder.finish().unwrap())).unwrap());
+}
+
+#[test]
+fn read_tar_xz() {
+    let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+    encoder.write_all(&tarball()).unwrap();
+    check(Archive::new(Cursor::new(encoder.finish().unwrap())).unwrap());
+}
+
+#[test]
+fn read_tar_bz2() {
+    let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+    encoder.write_all(&tarball()).unwrap();
+    check(Archive::new(Cursor::new(encoder.finish().unwrap())).unwrap());
+}
+
+#[test]
+fn read_member_with_several_descriptors() {
+    let text = format!("{}{}", SAMPLE, SAMPLE.replacen("03:02:01", "04:02:01", 1));
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(text.len() as u64);
+    header.set_mode(0o644);
+    builder.append_data(&mut header, "exit-list-2016-08/2016-08-06", text.as_bytes()).unwrap();
+
+    let mut archive = Archive::new(Cursor::new(builder.into_inner().unwrap())).unwrap();
+    let member = archive.members().unwrap().next().unwrap().unwrap();
+    let downloaded: Vec<_> = member.parse().map(|descriptor| match descriptor {
+        Ok(Descriptor::ExitList(el)) => el.downloaded.unwrap(),
+        other => panic!("unexpected {:?}", other),
+    }).collect();
+    assert_eq!(downloaded, vec!["2016-08-06 03:02:01", "2016-08-06 04:02:01"]);
+}
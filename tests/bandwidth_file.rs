@@ -0,0 +1,74 @@
+extern crate tordesc;
+
+use tordesc::bandwidth_file::*;
+
+static SAMPLE: &str = r#"@type bandwidth-file 1.0
+1568613366
+version=1.4.0
+earliest_bandwidth=2019-09-11T05:55:53
+file_created=2019-09-16T05:56:24
+generator_started=2019-09-13T16:36:52
+latest_bandwidth=2019-09-16T05:56:06
+number_consensus_relays=6551
+software=sbws
+software_version=1.1.0
+=====
+bw=1 error_circ=0 master_key_ed25519=Ho7bEGC8mhNHy6OR8sn7ok4Zd5qVxQNq/sEHNEFnuU8 nick=TorRelay node_id=$0011BD2485AD45D984EC4159C88FC066E5E3300E unmeasured=1 vote=0
+bw=7340 error_circ=0 master_key_ed25519=SPszNQjXl1EkJLiFdF5wFv2ZqgN8Ba0qIgR45ofdHYk nick=seele node_id=$000A10D43011EA4928A35F610405F92B4433B4DC success=58
+"#;
+
+#[test]
+fn parse_bandwidth_file() {
+    let bf = parse(SAMPLE).unwrap();
+    assert_eq!(bf.timestamp,          1568613366);
+    assert_eq!(bf.version,            Some("1.4.0"));
+    assert_eq!(bf.software,           Some("sbws"));
+    assert_eq!(bf.software_version,   Some("1.1.0"));
+    assert_eq!(bf.file_created,       Some("2019-09-16T05:56:24"));
+    assert_eq!(bf.earliest_bandwidth, Some("2019-09-11T05:55:53"));
+    assert_eq!(bf.latest_bandwidth,   Some("2019-09-16T05:56:06"));
+    assert_eq!(bf.unprocessed_keys, vec![
+        ("generator_started", "2019-09-13T16:36:52"),
+        ("number_consensus_relays", "6551"),
+    ]);
+}
+
+#[test]
+fn parse_relay_lines() {
+    let bf = parse(SAMPLE).unwrap();
+    assert_eq!(bf.relays.len(), 2);
+    let rb = &bf.relays[1];
+    assert_eq!(rb.node_id, "$000A10D43011EA4928A35F610405F92B4433B4DC");
+    assert_eq!(rb.nick,    Some("seele"));
+    assert_eq!(rb.bw,      Some(7340));
+    assert_eq!(rb.master_key_ed25519, Some("SPszNQjXl1EkJLiFdF5wFv2ZqgN8Ba0qIgR45ofdHYk"));
+    assert_eq!(rb.unprocessed_keys, vec![("error_circ", "0"), ("success", "58")]);
+}
+
+#[test]
+fn parse_version_1_0_0() {
+    // the original format has no header besides the timestamp, and no terminator
+    let bf = parse(r#"@type bandwidth-file 1.0
+1523911758
+node_id=$68A483E05A2ABDCA6DA5A3EF8DB5177638A27F80 bw=760 nick=Test measured_at=1523911725
+"#).unwrap();
+    assert_eq!(bf.version, None);
+    assert_eq!(bf.relays[0].bw, Some(760));
+}
+
+#[test]
+fn parse_all_bandwidth_files() {
+    let doubled = format!("{}{}", SAMPLE, SAMPLE);
+    assert_eq!(parse_all(&doubled).len(), 2);
+}
+
+#[test]
+fn parse_each_bandwidth_file() {
+    let broken = SAMPLE.replacen("1568613366", "%1568613366", 1);
+    let input = format!("{}{}{}", SAMPLE, broken, SAMPLE);
+    let results: Vec<_> = parse_each(&input).collect();
+    assert_eq!(results.len(), 3);
+    assert!(results[1].is_err());
+    assert_eq!(results[2].as_ref().unwrap().timestamp, 1568613366);
+    assert_eq!(parse_all(&input).len(), 2);
+}
@@ -0,0 +1,62 @@
+extern crate tordesc;
+
+use tordesc::data_directory::*;
+use tordesc::document::Annotation;
+
+static MICRODESCRIPTOR: &str = include_str!("data/microdescriptor");
+
+// As in Tor's cached-microdescs file, where each descriptor has a @last-listed annotation instead
+// of a @type one.
+fn cached_microdescs_file() -> String {
+    ["2016-08-06 03:00:00", "2016-08-06 04:00:00"].iter()
+        .map(|listed| MICRODESCRIPTOR.replacen("@type microdescriptor 1.0", &format!("@last-listed {}", listed), 1))
+        .collect()
+}
+
+static CACHED_MICRODESC_CONSENSUS: &str = r#"network-status-version 3 microdesc
+vote-status consensus
+consensus-method 25
+valid-after 2016-08-06 03:00:00
+fresh-until 2016-08-06 04:00:00
+valid-until 2016-08-06 06:00:00
+voting-delay 300 300
+known-flags Authority BadExit Exit Fast Guard HSDir Running Stable V2Dir Valid
+r LetFreedomRing 2kzsk8jS8YfAJ6ltOSXBUx2QqJ4 2016-08-05 21:10:27 24.233.74.111 9001 0
+m IR/ViEHxVCg6oWlstqvVwkV8WLC0nWvs7zZ6kIeSsXU
+s Exit Fast Running Stable Valid
+v Tor 0.2.6.1-alpha
+w Bandwidth=16
+p accept 22,465,993-995,6660-6697
+directory-footer
+"#;
+
+#[test]
+fn parse_cached_microdescs() {
+    let file = cached_microdescs_file();
+    let mds: Vec<_> = cached_microdescs(&file).map(Result::unwrap).collect();
+    assert_eq!(mds.len(), 2);
+    assert_eq!(mds[0].family, vec!["$0011BD2485AD45D984EC4159C88FC066E5E3300E", "LetFreedomRing"]);
+    assert_eq!(mds[1].type_annotation, None);
+    assert_eq!(mds[1].annotations, vec![
+        Annotation { key: "last-listed", args: Some("2016-08-06 04:00:00") },
+    ]);
+}
+
+#[test]
+fn parse_cached_microdescs_journal() {
+    // a journal may end with a descriptor which was only partially written
+    let file = cached_microdescs_file();
+    let journal = &file[..file.rfind("-----END RSA PUBLIC KEY-----").unwrap()];
+    let results: Vec<_> = cached_microdescs(journal).collect();
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    assert!(results[1].is_err());
+}
+
+#[test]
+fn parse_cached_microdesc_consensus() {
+    let c = cached_microdesc_consensus(CACHED_MICRODESC_CONSENSUS).unwrap();
+    assert_eq!(c.type_annotation, None);
+    assert_eq!(c.header.network_status_version, Some("3 microdesc"));
+    assert_eq!(c.routers[0].microdesc_digest().unwrap().base64(), "IR/ViEHxVCg6oWlstqvVwkV8WLC0nWvs7zZ6kIeSsXU");
+}
@@ -0,0 +1,80 @@
+extern crate tordesc;
+
+use tordesc::{parse_any, parse_each_any, Descriptor};
+use tordesc::ErrorKind;
+use tordesc::document::{DescriptorType, TypeAnnotation};
+
+#[test]
+fn parse_any_dispatches_on_type() {
+    let exit_list = r#"@type tordnsel 1.0
+Downloaded 2016-08-06 03:02:01
+ExitNode 0011BD2485AD45D984EC4159C88FC066E5E3300E
+Published 2016-08-05 20:06:12
+LastStatus 2016-08-06 02:02:56
+ExitAddress 162.247.72.201 2016-08-06 02:17:06
+"#;
+    match parse_any(exit_list) {
+        Ok(Descriptor::ExitList(el)) => assert_eq!(el.exit_nodes.len(), 1),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let torperf = "@type torperf 1.0\nFILESIZE=51200 SOURCE=moria START=1470456001.55\n";
+    match parse_any(torperf) {
+        Ok(Descriptor::Torperf(results)) => assert_eq!(results[0].source, Some("moria")),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn parse_any_minor_version() {
+    match parse_any("@type torperf 1.2\nFILESIZE=51200 SOURCE=moria START=1470456001.55\n") {
+        Ok(Descriptor::Torperf(results)) => {
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].type_annotation.unwrap().minor, 2);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn descriptor_types() {
+    let annotation = |name, major, minor| TypeAnnotation { name, major, minor };
+    assert_eq!(annotation("bridge-server-descriptor", 1, 2).descriptor_type(), Some(DescriptorType::ServerDescriptor));
+    assert_eq!(annotation("bridge-extra-info", 1, 3).descriptor_type(),        Some(DescriptorType::ExtraInfo));
+    assert_eq!(annotation("torperf", 1, 1).descriptor_type(),                  Some(DescriptorType::Torperf));
+    assert_eq!(annotation("server-descriptor", 2, 0).descriptor_type(),        None);
+    assert_eq!(annotation("network-status-2", 1, 0).descriptor_type(),         None);
+}
+
+#[test]
+fn parse_any_unsupported() {
+    let err = parse_any("@type snowflake-stats 1.0\n").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::UnsupportedType { name: "snowflake-stats".to_string(), major: 1, minor: 0 });
+    assert_eq!((err.line, err.column), (1, 1));
+
+    let err = parse_any("@type server-descriptor 2.0\n").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::UnsupportedType { name: "server-descriptor".to_string(), major: 2, minor: 0 });
+    assert_eq!(err.to_string(), "unsupported descriptor type @type server-descriptor 2.0 at line 1, column 1 in \"@type\"");
+}
+
+#[test]
+fn parse_any_errors() {
+    assert_eq!(parse_any("router seele 67.161.31.147 9001 0 0\n").unwrap_err().kind,
+               ErrorKind::BadAnnotation);
+    assert_eq!(parse_any("@type tordnsel 1.0\n").unwrap_err().kind, ErrorKind::UnexpectedEnd);
+}
+
+#[test]
+fn parse_each_any_splits_documents() {
+    let torperf = "@type torperf 1.0\nFILESIZE=51200 SOURCE=moria START=1470456001.55\n";
+    let input = format!("{}{}{}", torperf, torperf.replacen("SOURCE=moria", "SOURCE moria", 1), torperf);
+    let results: Vec<_> = parse_each_any(&input).collect();
+    assert_eq!(results.len(), 3);
+    assert!(matches!(results[0], Ok(Descriptor::Torperf(_))));
+    assert_eq!(results[1].as_ref().unwrap_err().kind, ErrorKind::MalformedKeyValue);
+    assert!(matches!(results[2], Ok(Descriptor::Torperf(_))));
+
+    let results: Vec<_> = parse_each_any("@type snowflake-stats 1.0\n").collect();
+    assert_eq!(results.len(), 1);
+    assert!(results[0].is_err());
+}
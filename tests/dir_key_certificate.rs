@@ -0,0 +1,71 @@
+extern crate tordesc;
+
+use tordesc::dir_key_certificate::*;
+
+static SAMPLE: &str = r#"@type dir-key-certificate-3 1.0
+dir-key-certificate-version 3
+dir-address 86.59.21.38:80
+fingerprint 14C131DFC5C6F93646BE72FA1401C02A8DF2E8B4
+dir-key-published 2016-04-19 13:02:19
+dir-key-expires 2016-12-19 13:02:19
+dir-identity-key
+-----BEGIN RSA PUBLIC KEY-----
+MIIBigKCAYEA0TLNwwpmfbVMRmMuOrKE8YyLK2nKmpqH8k5HL4nWhJ3f9Wt6nUaS
+QeJtQAWvOwfXkUCBjTUHh1JyzRsCFAbgcG6sXEEuq7X1VvCh8FjKN5CL4CXDEcw5
+-----END RSA PUBLIC KEY-----
+dir-signing-key
+-----BEGIN RSA PUBLIC KEY-----
+MIGJAoGBALT8X4cX54l93CRMzEJiVT7qKwLUc9zP3TyHE+cvUsVvGeNHBdtyCYSA
+tVBqYHMFXIK2twiu2dn+NuGgw7nSK2zQd4atVOTm+j0iMvSmgxbWM1hs1FnBpkpU
+-----END RSA PUBLIC KEY-----
+dir-key-crosscert
+-----BEGIN ID SIGNATURE-----
+kG0DSjfRYVa4Gm0LRSH6RhECTzvD9hPLHnHiLqvLR8Mwa3gVjQFIE/H8ZlYCbc6x
+-----END ID SIGNATURE-----
+dir-key-certification
+-----BEGIN SIGNATURE-----
+ivBbmiDwvXYCY8+tD8VdlgHVNNvT8h27AhaWDwp1NMmqzHBsItawAMxg9XNm3Nhr
+-----END SIGNATURE-----
+"#;
+
+#[test]
+fn parse_key_certificate() {
+    let kc = parse(SAMPLE).unwrap();
+    assert_eq!(kc.version,     Some(3));
+    assert_eq!(kc.address,     Some("86.59.21.38:80"));
+    assert_eq!(kc.fingerprint, Some("14C131DFC5C6F93646BE72FA1401C02A8DF2E8B4"));
+    assert_eq!(kc.published,   Some("2016-04-19 13:02:19"));
+    assert_eq!(kc.expires,     Some("2016-12-19 13:02:19"));
+    assert!(kc.unprocessed_items.is_empty());
+}
+
+#[test]
+fn parse_key_certificate_objects() {
+    let kc = parse(SAMPLE).unwrap();
+
+    let identity_key = kc.identity_key.unwrap();
+    assert_eq!(identity_key.keyword, "RSA PUBLIC KEY");
+    assert!(identity_key.text.starts_with("-----BEGIN RSA PUBLIC KEY-----\nMIIBigKCAYEA"));
+    assert!(identity_key.text.ends_with("-----END RSA PUBLIC KEY-----\n"));
+
+    assert_eq!(kc.signing_key.unwrap().keyword,   "RSA PUBLIC KEY");
+    assert_eq!(kc.crosscert.unwrap().keyword,     "ID SIGNATURE");
+    assert_eq!(kc.certification.unwrap().keyword, "SIGNATURE");
+}
+
+#[test]
+fn parse_all_key_certificates() {
+    let doubled = format!("{}{}", SAMPLE, SAMPLE);
+    assert_eq!(parse_all(&doubled).len(), 2);
+}
+
+#[test]
+fn parse_each_key_certificate() {
+    let broken = SAMPLE.replacen("dir-address", "%dir-address", 1);
+    let input = format!("{}{}{}", SAMPLE, broken, SAMPLE);
+    let results: Vec<_> = parse_each(&input).collect();
+    assert_eq!(results.len(), 3);
+    assert!(results[1].is_err());
+    assert_eq!(results[2].as_ref().unwrap().version, Some(3));
+    assert_eq!(parse_all(&input).len(), 2);
+}
@@ -0,0 +1,79 @@
+extern crate tordesc;
+
+use std::net::Ipv4Addr;
+use tordesc::digest::Digest;
+use tordesc::exit_list::*;
+
+static SAMPLE: &str = r#"@type tordnsel 1.0
+Downloaded 2016-08-06 03:02:01
+ExitNode 0011BD2485AD45D984EC4159C88FC066E5E3300E
+Published 2016-08-05 20:06:12
+LastStatus 2016-08-06 02:02:56
+ExitAddress 162.247.72.201 2016-08-06 02:17:06
+ExitNode 0098C475875ABC4AA864738B1D1079F711C38287
+Published 2016-08-05 13:47:40
+LastStatus 2016-08-06 02:02:56
+ExitAddress 162.248.11.176 2016-08-05 14:26:14
+ExitAddress 162.248.11.177 2016-08-06 01:39:12
+"#;
+
+#[test]
+fn parse_exit_list() {
+    let el = parse(SAMPLE).unwrap();
+    assert_eq!(el.downloaded, Some("2016-08-06 03:02:01"));
+    assert_eq!(el.exit_nodes.len(), 2);
+    assert!(el.unprocessed_items.is_empty());
+}
+
+#[test]
+fn parse_exit_nodes() {
+    let el = parse(SAMPLE).unwrap();
+    let en = &el.exit_nodes[1];
+    assert_eq!(en.fingerprint.as_ref().unwrap().hex(), "0098C475875ABC4AA864738B1D1079F711C38287");
+    assert_eq!(en.published,   Some("2016-08-05 13:47:40"));
+    assert_eq!(en.last_status, Some("2016-08-06 02:02:56"));
+    assert_eq!(en.exit_addresses, vec![
+        ExitAddress { address: Ipv4Addr::new(162, 248, 11, 176), observed: "2016-08-05 14:26:14" },
+        ExitAddress { address: Ipv4Addr::new(162, 248, 11, 177), observed: "2016-08-06 01:39:12" },
+    ]);
+}
+
+#[test]
+fn index_exit_nodes_by_fingerprint() {
+    let index = parse(SAMPLE).unwrap().index_by_fingerprint();
+    // looked up by the fingerprint of the relay's server descriptor
+    let fingerprint = Digest::from_hex("0011 BD24 85AD 45D9 84EC 4159 C88F C066 E5E3 300E").unwrap();
+    let en = &index[&fingerprint];
+    assert_eq!(en.exit_addresses[0].address, Ipv4Addr::new(162, 247, 72, 201));
+}
+
+#[test]
+fn index_skips_invalid_fingerprints() {
+    let input = SAMPLE
+        .replacen("0011BD2485AD45D984EC4159C88FC066E5E3300E", "0011BD2485AD", 1)
+        .replacen("0098C475875ABC4AA864738B1D1079F711C38287", "+098C475875ABC4AA864738B1D1079F711C38287", 1);
+    let el = parse(&input).unwrap();
+    assert_eq!(el.exit_nodes.len(), 2);
+    for en in &el.exit_nodes {
+        assert_eq!(en.fingerprint, None);
+        assert_eq!(en.unprocessed_items[0].key, "ExitNode");
+    }
+    assert!(el.index_by_fingerprint().is_empty());
+}
+
+#[test]
+fn parse_all_exit_lists() {
+    let doubled = format!("{}{}", SAMPLE, SAMPLE);
+    assert_eq!(parse_all(&doubled).len(), 2);
+}
+
+#[test]
+fn parse_each_exit_list() {
+    let broken = SAMPLE.replacen("Downloaded", "%Downloaded", 1);
+    let input = format!("{}{}{}", SAMPLE, broken, SAMPLE);
+    let results: Vec<_> = parse_each(&input).collect();
+    assert_eq!(results.len(), 3);
+    assert!(results[1].is_err());
+    assert_eq!(results[2].as_ref().unwrap().exit_nodes.len(), 2);
+    assert_eq!(parse_all(&input).len(), 2);
+}
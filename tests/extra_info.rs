@@ -0,0 +1,144 @@
+extern crate tordesc;
+
+use tordesc::extra_info::*;
+
+static SAMPLE: &str = r#"@type extra-info 1.0
+extra-info FlashHeart 0011BD2485AD45D984EC4159C88FC066E5E3300E
+published 2016-08-06 03:06:03
+write-history 2016-08-06 02:24:49 (14400 s) 55193600,58788864,57828352,61238272,57322496,54617088
+read-history 2016-08-06 02:24:49 (14400 s) 62196736,65495040,63976448,67722240,63621120,60709888
+dirreq-write-history 2016-08-06 02:24:49 (14400 s) 1012736,1012736,1016832,1033216,1017856,1011712
+dirreq-read-history 2016-08-06 02:24:49 (14400 s)
+geoip-db-digest 6346E26E2BC96F8511588CE2695E9B0339A75D32
+geoip6-db-digest 43CCB43DBC653D8CC16396A882C5F116A6004F0C
+dirreq-stats-end 2016-08-05 18:55:28 (86400 s)
+dirreq-v3-ips us=16,de=8,fr=8
+dirreq-v3-reqs us=24,de=8,fr=8
+dirreq-v3-resp ok=40,not-enough-sigs=0,unavailable=0,not-found=0,not-modified=0,busy=0
+dirreq-v3-direct-dl complete=0,timeout=0,running=0
+dirreq-v3-tunneled-dl complete=36,timeout=0,running=0,min=9186,md=88722,max=4135780
+entry-stats-end 2016-08-05 18:55:28 (86400 s)
+entry-ips
+hidserv-stats-end 2016-08-05 18:55:28 (86400 s)
+hidserv-rend-relayed-cells -3014 delta_f=2048 epsilon=0.30 bin_size=1024
+hidserv-dir-onions-seen 10 delta_f=8 epsilon=0.30 bin_size=8
+router-signature
+-----BEGIN SIGNATURE-----
+i9hLGP8mTaGHSKSDAKAT6Pe3aXE/HsBAmCqmefAf7y/XxAvoTKz8+ZTdUP2FmQaN
+YbHcJCOe/S/9C+8FkKdu5PXsSi3cPpIh7kVpD7pQXb4JjXlLxPoi/a7yrQeDUu5o
+mXV7TeaoQgG7KIyKNCMV5D2Tfeq/5dBw3uyhKxUBbWM=
+-----END SIGNATURE-----
+"#;
+
+#[test]
+fn parse_extra_info() {
+    let ei = parse(SAMPLE).unwrap();
+    assert_eq!(ei.nickname,     "FlashHeart");
+    assert_eq!(ei.fingerprint,  "0011BD2485AD45D984EC4159C88FC066E5E3300E");
+    assert_eq!(ei.published,    Some("2016-08-06 03:06:03"));
+}
+
+#[test]
+fn parse_history() {
+    let ei = parse(SAMPLE).unwrap();
+    assert_eq!(ei.read_history, Some(History {
+        end: "2016-08-06 02:24:49",
+        interval: 14400,
+        values: vec![62196736, 65495040, 63976448, 67722240, 63621120, 60709888],
+    }));
+    assert_eq!(ei.write_history.unwrap().values.len(), 6);
+    // a history with no intervals yet is still valid
+    assert_eq!(ei.dirreq_read_history.unwrap().values, vec![]);
+}
+
+#[test]
+fn parse_geoip_db_digests() {
+    let ei = parse(SAMPLE).unwrap();
+    assert_eq!(ei.geoip_db_digest,  Some("6346E26E2BC96F8511588CE2695E9B0339A75D32"));
+    assert_eq!(ei.geoip6_db_digest, Some("43CCB43DBC653D8CC16396A882C5F116A6004F0C"));
+}
+
+#[test]
+fn parse_dirreq_stats() {
+    let ei = parse(SAMPLE).unwrap();
+    assert_eq!(ei.dirreq_stats_end, Some(StatsInterval {
+        end: "2016-08-05 18:55:28",
+        interval: 86400,
+    }));
+    assert_eq!(ei.dirreq_v3_ips,  Some(vec![("us", 16), ("de", 8), ("fr", 8)]));
+    assert_eq!(ei.dirreq_v3_resp.unwrap()[1], ("not-enough-sigs", 0));
+    assert_eq!(ei.dirreq_v3_tunneled_dl.unwrap().last(), Some(&("max", 4135780)));
+}
+
+#[test]
+fn parse_empty_counts() {
+    assert_eq!(parse(SAMPLE).unwrap().entry_ips, Some(vec![]));
+}
+
+#[test]
+fn parse_unprocessed_items() {
+    let ei = parse(SAMPLE).unwrap();
+    let keys: Vec<_> = ei.unprocessed_items.iter().map(|i| i.key).collect();
+    assert_eq!(keys, vec![
+        "hidserv-stats-end",
+        "hidserv-rend-relayed-cells",
+        "hidserv-dir-onions-seen",
+    ]);
+}
+
+#[test]
+fn parse_partial_lists_unprocessed() {
+    let input = SAMPLE
+        .replacen("dirreq-v3-ips us=16,de=8,fr=8", "dirreq-v3-ips us=8,de=x,fr=16", 1)
+        .replacen("(14400 s) 55193600,58788864,", "(14400 s) 55193600,x,", 1);
+    let ei = parse(&input).unwrap();
+    assert_eq!(ei.dirreq_v3_ips, None);
+    assert!(ei.write_history.is_none());
+    let keys: Vec<_> = ei.unprocessed_items.iter().map(|i| i.key).collect();
+    assert_eq!(&keys[..2], &["write-history", "dirreq-v3-ips"]);
+}
+
+#[test]
+fn extra_info_digests() {
+    let ei = parse(SAMPLE).unwrap();
+    assert_eq!(ei.digest_sha1().unwrap().hex(), "272AFDAEE2EFD244A7912CDF193747579BAE54CA");
+    assert_eq!(ei.digest_sha256().base64(),     "6JptfMm/uNLPe0EtZTP27E47mbr5YOiL6KvcejKSQ8c");
+}
+
+#[test]
+fn parse_all_extra_infos() {
+    let doubled = format!("{}{}", SAMPLE, SAMPLE);
+    assert_eq!(parse_all(&doubled).len(), 2);
+}
+
+#[test]
+fn parse_bridge_extra_info() {
+    let ei = parse(r#"@type bridge-extra-info 1.3
+extra-info Unnamed 4A0CCD2DDC7995083D73F5D667100C8A5831F16D
+master-key-ed25519 RP3n42wLxmhBGZ1DfrqS8O+nwT9rxb9dFV8xUowR9ak
+published 2016-08-06 02:51:18
+transport obfs4
+transport meek
+bridge-stats-end 2016-08-05 14:25:01 (86400 s)
+bridge-ips de=8,ru=8,us=8
+bridge-ip-versions v4=16,v6=0
+bridge-ip-transports <OR>=8,obfs4=8
+router-digest-sha256 VQ0tqcJZo3y3BeBsO1U1kxXiv7Hx9ghKZdIMoiK4VM0
+router-digest 62D2D4F9B4E4A01D1A2A5F1C1B9B4CEB3F2C4F97
+"#).unwrap();
+    assert_eq!(ei.nickname,   "Unnamed");
+    assert_eq!(ei.transports, vec!["obfs4", "meek"]);
+    assert_eq!(ei.bridge_ip_transports, Some(vec![("<OR>", 8), ("obfs4", 8)]));
+    assert_eq!(ei.router_digest, Some("62D2D4F9B4E4A01D1A2A5F1C1B9B4CEB3F2C4F97"));
+    assert!(ei.router_digest_sha256.is_some());
+}
+
+#[test]
+fn parse_cached_extra_info() {
+    // as in Tor's cached-extrainfo file, with no @type
+    let cached = SAMPLE.replacen("@type extra-info 1.0\n", "", 1);
+    let ei = parse(&cached).unwrap();
+    assert_eq!(ei.nickname, "FlashHeart");
+    assert_eq!(ei.type_annotation, None);
+    assert!(ei.annotations.is_empty());
+}
@@ -0,0 +1,107 @@
+extern crate tordesc;
+
+use std::net::Ipv4Addr;
+use tordesc::hidden_service::v2::*;
+
+static SAMPLE: &str = r#"@type hidden-service-descriptor 1.0
+rendezvous-service-descriptor y3olqqblqw2gbh6phimfuiroechjjafa
+version 2
+permanent-key
+-----BEGIN RSA PUBLIC KEY-----
+MIGJAoGBAK2Ymm3bdeyOxTjrRpIjwdfSDSs3E5DLJU6t6gRnbqkMFVBCYBP2Q3E4
+4R7fNqVKMzf8lCEjI1QK1KwiD4T8ix52MCR6IPNFy8ga0B7D9x3JRIpuyAVI+j2L
+RLELH8Gvdr+qxuLykiAi+J29PtZuLRUfeNRsBhDG/9Gtu9Z+oFNnAgMBAAE=
+-----END RSA PUBLIC KEY-----
+secret-id-part e24kgecavwsznj7gpbktqsiwgvngsf4e
+publication-time 2015-02-23 20:00:00
+protocol-versions 2,3
+introduction-points
+-----BEGIN MESSAGE-----
+aW50cm9kdWN0aW9uLXBvaW50IGl3a2k3N3h0YnZwNnF2ZWRmcndkem5jeHMzY2th
+eWV1CmlwLWFkZHJlc3MgMTc4LjYyLjIyMi4xMjkKb25pb24tcG9ydCA0NDMKb25p
+b24ta2V5Ci0tLS0tQkVHSU4gUlNBIFBVQkxJQyBLRVktLS0tLQpNSUdKQW9HQkFL
+MlltbTNiZGV5T3hUanJScElqd2RmU0RTczNFNURMSlU2dDZnUm5icWtNRlZCQ1lC
+UDJRM0U0CjRSN2ZOcVZLTXpmOGxDRWpJMVFLMUt3aUQ0VDhpeDUyTUNSNklQTkZ5
+OGdhMEI3RDl4M0pSSXB1eUFWSStqMkwKUkxFTEg4R3ZkcitxeHVMeWtpQWkrSjI5
+UHRadUxSVWZlTlJzQmhERy85R3R1OVorb0ZObkFnTUJBQUU9Ci0tLS0tRU5EIFJT
+QSBQVUJMSUMgS0VZLS0tLS0Kc2VydmljZS1rZXkKLS0tLS1CRUdJTiBSU0EgUFVC
+TElDIEtFWS0tLS0tCk1JR0pBb0dCQUsyWW1tM2JkZXlPeFRqclJwSWp3ZGZTRFNz
+M0U1RExKVTZ0NmdSbmJxa01GVkJDWUJQMlEzRTQKNFI3Zk5xVktNemY4bENFakkx
+UUsxS3dpRDRUOGl4NTJNQ1I2SVBORnk4Z2EwQjdEOXgzSlJJcHV5QVZJK2oyTApS
+TEVMSDhHdmRyK3F4dUx5a2lBaStKMjlQdFp1TFJVZmVOUnNCaERHLzlHdHU5Witv
+Rk5uQWdNQkFBRT0KLS0tLS1FTkQgUlNBIFBVQkxJQyBLRVktLS0tLQppbnRyb2R1
+Y3Rpb24tcG9pbnQgZW00Z2prNmVpaXVhbGhtbHlpaWZyemM3bGJ0cnNiaXAKaXAt
+YWRkcmVzcyA0Ni40LjE3NC41Mgpvbmlvbi1wb3J0IDkwMDEKb25pb24ta2V5Ci0t
+LS0tQkVHSU4gUlNBIFBVQkxJQyBLRVktLS0tLQpNSUdKQW9HQkFLMlltbTNiZGV5
+T3hUanJScElqd2RmU0RTczNFNURMSlU2dDZnUm5icWtNRlZCQ1lCUDJRM0U0CjRS
+N2ZOcVZLTXpmOGxDRWpJMVFLMUt3aUQ0VDhpeDUyTUNSNklQTkZ5OGdhMEI3RDl4
+M0pSSXB1eUFWSStqMkwKUkxFTEg4R3ZkcitxeHVMeWtpQWkrSjI5UHRadUxSVWZl
+TlJzQmhERy85R3R1OVorb0ZObkFnTUJBQUU9Ci0tLS0tRU5EIFJTQSBQVUJMSUMg
+S0VZLS0tLS0Kc2VydmljZS1rZXkKLS0tLS1CRUdJTiBSU0EgUFVCTElDIEtFWS0t
+LS0tCk1JR0pBb0dCQUsyWW1tM2JkZXlPeFRqclJwSWp3ZGZTRFNzM0U1RExKVTZ0
+NmdSbmJxa01GVkJDWUJQMlEzRTQKNFI3Zk5xVktNemY4bENFakkxUUsxS3dpRDRU
+OGl4NTJNQ1I2SVBORnk4Z2EwQjdEOXgzSlJJcHV5QVZJK2oyTApSTEVMSDhHdmRy
+K3F4dUx5a2lBaStKMjlQdFp1TFJVZmVOUnNCaERHLzlHdHU5WitvRk5uQWdNQkFB
+RT0KLS0tLS1FTkQgUlNBIFBVQkxJQyBLRVktLS0tLQo=
+-----END MESSAGE-----
+signature
+-----BEGIN SIGNATURE-----
+aFP0JmBQHW1eG3vjULkdgpIxCqrKXdaxF7uKVRgN2rZsSLZJDTvcXswE6tGyBCHg
+UrZbs+9UMhrXyGZHiXjpKBbeY7kX7UK5SL2IXMBPymJPzCEg/uUL0j4jI2l7KTnG
+UF/FUnhy6Qm7Ab/ArvqKEQJwO10chKHEaOu+wDFoE8s=
+-----END SIGNATURE-----
+"#;
+
+#[test]
+fn parse_hs_descriptor() {
+    let hsd = parse(SAMPLE).unwrap();
+    assert_eq!(hsd.descriptor_id,     Some("y3olqqblqw2gbh6phimfuiroechjjafa"));
+    assert_eq!(hsd.version,           Some(2));
+    assert_eq!(hsd.secret_id_part,    Some("e24kgecavwsznj7gpbktqsiwgvngsf4e"));
+    assert_eq!(hsd.published,         Some("2015-02-23 20:00:00"));
+    assert_eq!(hsd.protocol_versions, vec![2, 3]);
+    assert!(hsd.permanent_key.unwrap().starts_with("-----BEGIN RSA PUBLIC KEY-----"));
+    assert!(hsd.signature.unwrap().starts_with("-----BEGIN SIGNATURE-----"));
+    assert!(hsd.unprocessed_items.is_empty());
+}
+
+#[test]
+fn parse_introduction_points() {
+    let hsd = parse(SAMPLE).unwrap();
+    let ips = hsd.introduction_points().unwrap();
+    assert_eq!(ips.len(), 2);
+    assert_eq!(ips[0].identifier, "iwki77xtbvp6qvedfrwdzncxs3ckayeu");
+    assert_eq!(ips[0].address,    Some(Ipv4Addr::new(178, 62, 222, 129)));
+    assert_eq!(ips[0].port,       Some(443));
+    assert!(ips[0].onion_key.is_some());
+    assert!(ips[0].service_key.is_some());
+    assert_eq!(ips[1].identifier, "em4gjk6eiiualhmlyiifrzc7lbtrsbip");
+    assert_eq!(ips[1].port,       Some(9001));
+}
+
+#[test]
+fn parse_encrypted_introduction_points() {
+    // with client authorization, the decoded object is not a document of items
+    let encrypted = SAMPLE.replace("introduction-points\n-----BEGIN MESSAGE-----\n",
+                                   "introduction-points\n-----BEGIN MESSAGE-----\nAgEdbps604RR\n");
+    let hsd = parse(&encrypted).unwrap();
+    assert!(hsd.introduction_points.is_some());
+    assert!(hsd.introduction_points().is_none());
+}
+
+#[test]
+fn parse_all_hs_descriptors() {
+    let doubled = format!("{}{}", SAMPLE, SAMPLE);
+    assert_eq!(parse_all(&doubled).len(), 2);
+}
+
+#[test]
+fn parse_each_hs_descriptor() {
+    let broken = SAMPLE.replacen("version 2", "%version 2", 1);
+    let input = format!("{}{}{}", SAMPLE, broken, SAMPLE);
+    let results: Vec<_> = parse_each(&input).collect();
+    assert_eq!(results.len(), 3);
+    assert!(results[1].is_err());
+    assert_eq!(results[2].as_ref().unwrap().version, Some(2));
+    assert_eq!(parse_all(&input).len(), 2);
+}
@@ -0,0 +1,63 @@
+extern crate tordesc;
+
+use tordesc::hidden_service::v3::*;
+
+static SAMPLE: &str = r#"@type hidden-service-descriptor-3 1.0
+hs-descriptor 3
+descriptor-lifetime 180
+descriptor-signing-key-cert
+-----BEGIN ED25519 CERT-----
+UvImZaYMEtKJGF2VDuiBNgkWb2sRPReNbA/TkB/yOaGglfIPk5VlDPk4C47bIkpr
+JIoekk6P0K4uGpSSozBfGIy2EJAPnjR/rohtxlB3lex0XEw/yy6yxz4Uk0yGfuBX
+unJJm/oSHoNrKsFXJu59awr2qxPDjpLK4NFQV7FZmH+UzHQR1xfxRXmyqhA=
+-----END ED25519 CERT-----
+revision-counter 42
+superencrypted
+-----BEGIN MESSAGE-----
+D7uzT6WT/q7Scki3YuOrWAXwdlornB1+DzfESSG9P2Vk6t9/FCpyZoxH4iPRbt2M
+R7Rq/Fuu4mH1OyYVLSY7qDsDfNSWLkNIASVriF6ckFHzILDbg/Oep629DXTm3sfz
+367Mj2RlZmQae6JmDzAR/DVwKRxXmQ0aAJEmiRnyXZ0GEt81nWAmokD0WJpdeR8d
+2Xz++nd6e08VJBq/V71DetSxKYQFNPPzh1wlsIvqBsKHTPqk3Rey2EKEXegqW8U5
+iIrHgFSiOZzPyfzC2jHOPdFmvc06M4R+W7sH/QfKR3hCMbGa9Fhyzu+5/Fn0+V0U
+OBo6eDJWNHuf/Oac1wB66KdYzKQV1ake6GPItsAzeuMtb8qiVRbN8vi4ZXZmvvIV
+uSgr/iAHJpfnd86n
+-----END MESSAGE-----
+signature JZzTmPp5qO9ZJ4yMIQUDzPi5phqGv+8jb/zfMdPfNgdANkqAPcOWU0KLa9UhD+i9WuV1qZXQ54Rr0+rggCGIJg
+"#;
+
+#[test]
+fn parse_hs_descriptor() {
+    let hsd = parse(SAMPLE).unwrap();
+    assert_eq!(hsd.version,          Some(3));
+    assert_eq!(hsd.lifetime,         Some(180));
+    assert_eq!(hsd.revision_counter, Some(42));
+    assert!(hsd.unprocessed_items.is_empty());
+}
+
+#[test]
+fn parse_hs_descriptor_blobs() {
+    let hsd = parse(SAMPLE).unwrap();
+    assert_eq!(hsd.signing_key_cert.unwrap().len(), 140);
+    assert_eq!(&hsd.superencrypted.unwrap()[..4], &[15, 187, 179, 79]);
+    assert_eq!(hsd.signature.unwrap().len(), 64);
+}
+
+#[test]
+fn parse_hs_descriptor_without_annotation() {
+    let hsd = parse(SAMPLE.split_once('\n').unwrap().1).unwrap();
+    assert_eq!(hsd.revision_counter, Some(42));
+    assert_eq!(hsd.superencrypted.unwrap().len(), 300);
+}
+
+#[test]
+fn parse_each_hs_descriptor() {
+    // descriptors without annotations are split at their hs-descriptor line
+    let bare = SAMPLE.split_once('\n').unwrap().1;
+    let broken = bare.replacen("revision-counter", "%revision-counter", 1);
+    let input = format!("{}{}{}", bare, broken, bare);
+    let results: Vec<_> = parse_each(&input).collect();
+    assert_eq!(results.len(), 3);
+    assert!(results[1].is_err());
+    assert_eq!(results[2].as_ref().unwrap().revision_counter, Some(42));
+    assert_eq!(parse_all(&input).len(), 2);
+}
@@ -1,5 +1,7 @@
 extern crate tordesc;
 
+use tordesc::ErrorKind;
+use tordesc::document::{Annotation, decode_base64};
 use tordesc::server_descriptor::*;
 use tordesc::server_descriptor::exit_policy::*;
 
@@ -7,54 +9,34 @@ use std::fs::File;
 use std::io::{Read,BufReader};
 use std::net::Ipv4Addr;
 use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
 
 // use the same sample as zoossh to try to ensure compatibility
-static SAMPLE: &'static str = r#"@type server-descriptor 1.0
-router LetFreedomRing 24.233.74.111 9001 0 0
-platform Tor 0.2.6.1-alpha on Linux
+static SAMPLE: &str = include_str!("data/server-descriptor");
+
+// a sanitized bridge descriptor, as published by CollecTor
+static BRIDGE_SAMPLE: &str = r#"@type bridge-server-descriptor 1.2
+router Unnamed 10.152.219.144 9001 0 0
+master-key-ed25519 RP3n42wLxmhBGZ1DfrqS8O+nwT9rxb9dFV8xUowR9ak
+platform Tor 0.2.8.6 on Linux
 protocols Link 1 2 Circuit 1
-published 2014-12-05 22:01:13
-fingerprint DA4D EC93 C8D2 F187 C027 A96D 3925 C153 1D90 A89E
-uptime 339587
-bandwidth 20480 20480 16996
-extra-info-digest 15FA36289DD75D89B389CED0BE23D80FB50629BD
-onion-key
------BEGIN RSA PUBLIC KEY-----
-MIGJAoGBALD6Dbj1okBj4mmz/sCgIGFJk/CTWlMsT3CS1kP7Q2gAaDewEbo1+me3
-X5f3QpvZ9Yh2l5Q+btU4a/Yib3pg/KhyX96Z5zrvz9dGPPXGORpwawMIH7Aa+jtp
-v2l0misfGCloIamfI5dzayTu9gR4emuKm34tipkfIz6hLkO7xW1nAgMBAAE=
------END RSA PUBLIC KEY-----
-signing-key
------BEGIN RSA PUBLIC KEY-----
-MIGJAoGBAM6sVv1ASHBuLe8l3+cF4xATk1n/CqNRqML0Gra0S9UaBnKakm9tk7Vw
-PJifL3B318lRDjAE2wTVyM+437TLaROLNBrQOF2apjgJYH661vPFG5Uw6+8CXv6w
-tHeXU1pvc/E7SA0IpUjm80z0HhSA3oGwuP4IEB1U1IxxiJNFaBk7AgMBAAE=
------END RSA PUBLIC KEY-----
+published 2016-08-06 02:51:18
+fingerprint 4A0C CD2D DC79 9508 3D73 F5D6 6710 0C8A 5831 F16D
+uptime 2411813
+bandwidth 1073741824 1073741824 3553812
+extra-info-digest 8DEB2C2EDC2F9E9E4CF40FA5FA2C6C7FE4BBB1C0 3CTsFN87l3DdM6QrQNHBVXnSvuhZg0z5SzJmN3UfHXM
 hidden-service-dir
-contact 0xCDD0190B Craig Andrews <candrews@integralblue.com>
-ntor-onion-key q8Qg9PaoBm59j7cEJcOrzTUazVt3D8Ax4L3oaO8PaxU=
-reject 0.0.0.0/8:*
-reject 169.254.0.0/16:*
-reject 127.0.0.0/8:*
-reject 192.168.0.0/16:*
-reject 10.0.0.0/8:*
-reject 172.16.0.0/12:*
-reject 24.233.74.111:*
-accept *:22
-accept *:465
-accept *:993
-accept *:994
-accept *:995
-accept *:6660-6697
+bridge-distribution-request any
+contact somebody
+ntor-onion-key ZqNuaXjGuCsKLF5pFqMWOukbyfvWfaIVBYMZTU+e+iU=
 reject *:*
-router-signature
------BEGIN SIGNATURE-----
-vKWlPhEDoRHOKgDNXE07HFl39b4SmGUDo8DStSzzza+CKVw2RnV41wYBpjRJvu2Q
-VcQb00bfqWP/DK38GmVMgzKRZ7e1k2TpzaeL3ssD3gS6wJPzbIbcL++yUhtPukk/
-tWJ53g/ru8Hiy+h9Wa5gI+Eog/z4hj36GBiaTXJoG3M=
------END SIGNATURE-----
+router-digest-sha256 hPvQ0wqfV9pWb/y6YYAFv6NNpsZqW6DfeJH4Y5PG27A
+router-digest 4C61A7EB8AD47B3B0CF88C12FB3F9D9E0BDD3A0F
 "#;
 
+// a modern relay descriptor, with ed25519 keys and crosscerts, signed with throwaway keys
+static ED25519_SAMPLE: &str = include_str!("data/server-descriptor-ed25519");
+
 #[test]
 fn dump_server_descriptor() {
     match parse(SAMPLE) {
@@ -79,7 +61,25 @@ fn parse_ed25519() {
     assert_eq!(sd.identity_ed25519, None);
     assert_eq!(sd.master_key_ed25519, None);
     assert_eq!(sd.router_sig_ed25519, None);
-    // TODO: our sample here does not have these fields, make a secondary sample that does
+    assert_eq!(sd.identity_cert(), None);
+
+    let sd = parse(ED25519_SAMPLE).unwrap();
+    assert_eq!(sd.master_key_ed25519, Some("6x5PZL/YwJSIYY+l6C/Oyk6zy0iJDIacMrtg3CMt1xU"));
+    assert_eq!(sd.router_sig_ed25519,
+               Some("RBjT4JRViZesbQs4cUkCbI0yVYmGULf9r1oaSGSkcyMC+cKuM7vuVL28MCEJE9ZGzvS+0p28av18p1YRIm9SDw"));
+}
+
+#[test]
+fn parse_identity_cert() {
+    let cert = parse(ED25519_SAMPLE).unwrap().identity_cert().unwrap();
+    assert_eq!(cert.version,    1);
+    assert_eq!(cert.cert_type,  tordesc::cert::SIGNING_KEY);
+    assert_eq!(cert.expiration, UNIX_EPOCH + Duration::from_secs(480000 * 3600));
+    assert_eq!(cert.key_type,   1);
+    assert_eq!(cert.certified_key.to_vec(), decode_base64("OXOkwCc/IGHS9+8WdlK6+A5cu8BwCQeZWO1x/abNXUQ").unwrap());
+    assert_eq!(cert.signing_key().unwrap().to_vec(),
+               decode_base64("6x5PZL/YwJSIYY+l6C/Oyk6zy0iJDIacMrtg3CMt1xU").unwrap());
+    assert_eq!(cert.signed().len(), 1 + 1 + 4 + 1 + 32 + 1 + (4 + 32));
 }
 
 #[test]
@@ -109,9 +109,21 @@ fn parse_published() {
 #[test]
 fn parse_fingerprint() {
     assert_eq!(
-        parse(SAMPLE).unwrap().fingerprint,
-        Some("DA4D EC93 C8D2 F187 C027 A96D 3925 C153 1D90 A89E")
+        parse(SAMPLE).unwrap().fingerprint.unwrap().spaced_hex(),
+        "DA4D EC93 C8D2 F187 C027 A96D 3925 C153 1D90 A89E"
     );
+
+    // a sign is not a hex digit, and a fingerprint is always 20 bytes
+    let bad_fingerprints = [
+        "+A4D EC93 C8D2 F187 C027 A96D 3925 C153 1D90 A89E",
+        "DA4D EC93 C8D2 F187 C027 A96D 3925 C153 1D90",
+    ];
+    for bad in &bad_fingerprints {
+        let input = SAMPLE.replacen("DA4D EC93 C8D2 F187 C027 A96D 3925 C153 1D90 A89E", bad, 1);
+        let sd = parse(&input).unwrap();
+        assert_eq!(sd.fingerprint, None);
+        assert!(sd.unprocessed_items.iter().any(|item| item.key == "fingerprint"));
+    }
 }
 
 #[test]
@@ -216,11 +228,276 @@ fn parse_exit_policy() {
     ));
 }
 
+#[test]
+fn parse_type_annotation() {
+    let annotation = parse(SAMPLE).unwrap().type_annotation.unwrap();
+    assert_eq!((annotation.name, annotation.major, annotation.minor), ("server-descriptor", 1, 0));
+}
+
+#[test]
+fn parse_any_minor_version() {
+    let newer = SAMPLE.replacen("@type server-descriptor 1.0", "@type server-descriptor 1.7", 1);
+    let sd = parse(&newer).unwrap();
+    assert_eq!(sd.nickname, "LetFreedomRing");
+    assert_eq!(sd.type_annotation.unwrap().minor, 7);
+
+    let doubled = format!("{}{}", newer, SAMPLE);
+    assert_eq!(parse_all(&doubled).len(), 2);
+
+    // a new major version may be incompatible, so is not accepted
+    let incompatible = SAMPLE.replacen("@type server-descriptor 1.0", "@type server-descriptor 2.0", 1);
+    assert!(parse(&incompatible).is_err());
+}
+
+#[test]
+fn parse_cached_descriptors() {
+    // as in Tor's cached-descriptors file, with no @type but other annotations
+    let cached = SAMPLE.replacen(
+        "@type server-descriptor 1.0\n",
+        "@downloaded-at 2016-08-06 03:06:03\n@source \"1.2.3.4\"\n",
+        1
+    );
+    let doubled = format!("{}{}", cached, cached);
+    let descriptors = parse_all(&doubled);
+    assert_eq!(descriptors.len(), 2);
+
+    let sd = &descriptors[1];
+    assert_eq!(sd.nickname,        "LetFreedomRing");
+    assert_eq!(sd.type_annotation, None);
+    assert_eq!(sd.annotations, vec![
+        Annotation { key: "downloaded-at", args: Some("2016-08-06 03:06:03") },
+        Annotation { key: "source",        args: Some("\"1.2.3.4\"") },
+    ]);
+}
+
+#[test]
+fn parse_annotations_after_type() {
+    let annotated = SAMPLE.replacen(
+        "@type server-descriptor 1.0\n",
+        "@type server-descriptor 1.0\n@downloaded-at 2016-08-06 03:06:03\n",
+        1
+    );
+    let sd = parse(&annotated).unwrap();
+    assert!(sd.type_annotation.is_some());
+    assert_eq!(sd.annotations[0].key, "downloaded-at");
+}
+
+#[test]
+fn parse_error_malformed_line() {
+    let broken = SAMPLE.replacen("uptime 339587\n", "uptime 339587\n%uptime\n", 1);
+    let err = parse(&broken).unwrap_err();
+    assert_eq!(err.kind,    ErrorKind::MalformedKeywordLine);
+    assert_eq!(err.line,    8);
+    assert_eq!(err.column,  1);
+    assert_eq!(err.keyword, Some("%uptime".to_string()));
+    assert_eq!(&broken[err.offset..err.offset + 8], "%uptime\n");
+    assert_eq!(err.to_string(), "malformed keyword line at line 8, column 1 in \"%uptime\"");
+}
+
+#[test]
+fn parse_error_unterminated_object() {
+    let broken = SAMPLE.replacen("-----END RSA PUBLIC KEY-----\nsigning-key", "signing-key", 1);
+    let err = parse(&broken).unwrap_err();
+    assert_eq!(err.kind,    ErrorKind::UnterminatedObject);
+    assert_eq!((err.line, err.column), (11, 1));
+    assert_eq!(err.keyword, Some("onion-key".to_string()));
+}
+
+#[test]
+fn parse_error_annotation() {
+    let err = parse(&SAMPLE.replacen("@type server-descriptor 1.0", "@type server-descriptor", 1)).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::BadAnnotation);
+    assert_eq!((err.line, err.column), (1, 1));
+
+    let err = parse(&SAMPLE.replacen("router ", "ruoter ", 1)).unwrap_err();
+    assert_eq!(err.kind,    ErrorKind::UnexpectedKeyword);
+    assert_eq!((err.line, err.column), (2, 1));
+    assert_eq!(err.keyword, Some("ruoter".to_string()));
+}
+
+#[test]
+fn parse_trailing_blank_lines() {
+    assert!(parse(&format!("{}\n", SAMPLE)).is_ok());
+    assert!(parse(&format!("{}\r\n\n", SAMPLE)).is_ok());
+}
+
+#[test]
+fn parse_error_trailing_text() {
+    // a single descriptor can't be followed by anything else, annotations included
+    let err = parse(&format!("{}@junk\n", SAMPLE)).unwrap_err();
+    assert_eq!(err.kind,    ErrorKind::BadAnnotation);
+    assert_eq!((err.line, err.column), (45, 1));
+    assert_eq!(err.keyword, Some("@junk".to_string()));
+}
+
+#[test]
+fn parse_error_after_blank_line() {
+    // reported at the next line that isn't blank, by its keyword
+    let err = parse(&SAMPLE.replacen("\nrouter ", "\n\nruoter ", 1)).unwrap_err();
+    assert_eq!(err.kind,    ErrorKind::UnexpectedKeyword);
+    assert_eq!((err.line, err.column), (3, 1));
+    assert_eq!(err.keyword, Some("ruoter".to_string()));
+
+    let broken = SAMPLE.replacen("-----END SIGNATURE-----\n", "", 1)
+                       .replacen("router-signature\n", "router-signature\n\n", 1);
+    let err = parse(&broken).unwrap_err();
+    assert_eq!(err.kind,    ErrorKind::UnterminatedObject);
+    assert_eq!(err.keyword, Some("router-signature".to_string()));
+}
+
+#[test]
+fn parse_each_recovers() {
+    let broken = SAMPLE.replacen("uptime 339587\n", "%uptime\n", 1);
+    let input = format!("{}{}{}", SAMPLE, broken, SAMPLE);
+    let results: Vec<_> = parse_each(&input).collect();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap().nickname, "LetFreedomRing");
+    assert_eq!(results[2].as_ref().unwrap().nickname, "LetFreedomRing");
+
+    let err = results[1].as_ref().unwrap_err();
+    assert_eq!(err.kind, ErrorKind::MalformedKeywordLine);
+    assert_eq!(err.line, SAMPLE.lines().count() + 7);
+    assert_eq!(&input[err.offset..err.offset + 7], "%uptime");
+
+    assert_eq!(parse_all(&input).len(), 2);
+}
+
+#[test]
+fn parse_each_without_annotations() {
+    let bare = SAMPLE.replacen("@type server-descriptor 1.0\n", "", 1);
+    let input = format!("{}{}", bare, bare.replacen("router LetFreedomRing", "router Unnamed", 1));
+    let nicknames: Vec<_> = parse_each(&input).map(|sd| sd.unwrap().nickname).collect();
+    assert_eq!(nicknames, vec!["LetFreedomRing", "Unnamed"]);
+}
+
+#[test]
+fn parse_each_blank_line_separators() {
+    let input = format!("\n{}\n{}\r\n\n", SAMPLE, SAMPLE);
+    let results: Vec<_> = parse_each(&input).collect();
+    assert_eq!(results.len(), 2);
+    for sd in &results {
+        assert_eq!(sd.as_ref().unwrap().nickname, "LetFreedomRing");
+    }
+
+    let bare = SAMPLE.replacen("@type server-descriptor 1.0\n", "", 1);
+    let input = format!("{}\n\n{}", bare, bare);
+    assert_eq!(parse_each(&input).filter(Result::is_ok).count(), 2);
+}
+
+#[test]
+fn server_descriptor_digests() {
+    let sd = parse(SAMPLE).unwrap();
+    let sha1 = sd.digest_sha1().unwrap();
+    assert_eq!(sha1.hex(),    "AE3D59E040E08DFA0D08FB0B0740C0B989B52B79");
+    assert_eq!(sha1.base64(), "rj1Z4EDgjfoNCPsLB0DAuYm1K3k");
+    // no ed25519 identity, so no router-sig-ed25519 to digest up to
+    assert_eq!(sd.digest_sha256(), None);
+
+    let sha256 = parse(ED25519_SAMPLE).unwrap().digest_sha256().unwrap();
+    assert_eq!(sha256.hex(),    "394CEBB42BDADDF7BF9852EB31F1FFF339DC9700B3FA82378E53C31A90BF4298");
+    assert_eq!(sha256.base64(), "OUzrtCva3fe/mFLrMfH/8znclwCz+oI3jlPDGpC/Qpg");
+
+    // annotations are not part of the descriptor
+    let cached = SAMPLE.replacen("@type server-descriptor 1.0\n", "@downloaded-at 2016-08-06 03:06:03\n", 1);
+    assert_eq!(parse(&cached).unwrap().digest_sha1(), Some(sha1));
+
+    // sanitized bridge descriptors have no signature to digest up to
+    assert_eq!(parse(BRIDGE_SAMPLE).unwrap().digest_sha1(), None);
+}
+
+#[test]
+fn identity_digest() {
+    let digest = parse(SAMPLE).unwrap().identity_digest().unwrap();
+    assert_eq!(digest.spaced_hex(), "DA4D EC93 C8D2 F187 C027 A96D 3925 C153 1D90 A89E");
+    assert_eq!(digest.base64(),     "2k3sk8jS8YfAJ6ltOSXBUx2QqJ4");
+}
+
+#[test]
+fn parse_crosscerts() {
+    let sd = parse(ED25519_SAMPLE).unwrap();
+    assert!(sd.onion_key_crosscert.unwrap().starts_with("-----BEGIN CROSSCERT-----\n"));
+
+    let crosscert = sd.ntor_onion_key_crosscert.unwrap();
+    assert_eq!(crosscert.sign, 1);
+    let cert = crosscert.cert().unwrap();
+    assert_eq!(cert.cert_type, tordesc::cert::NTOR_ONION_KEY);
+    assert_eq!(cert.certified_key.to_vec(),
+               decode_base64("6x5PZL/YwJSIYY+l6C/Oyk6zy0iJDIacMrtg3CMt1xU").unwrap());
+    assert!(sd.unprocessed_items.iter().all(|item| !item.key.contains("crosscert")));
+}
+
+#[test]
+fn validate() {
+    assert_eq!(parse(SAMPLE).unwrap().validate(), Ok(()));
+    assert_eq!(parse(ED25519_SAMPLE).unwrap().validate(), Ok(()));
+    assert_eq!(parse(BRIDGE_SAMPLE).unwrap().validate(), Ok(()));
+}
+
+#[test]
+fn validate_reports_every_violation() {
+    // no published line, a second bandwidth line, and a second contact line after the
+    // router-signature
+    let text = SAMPLE.replace("published 2014-12-05 22:01:13\n", "")
+                     .replace("uptime 339587\n", "bandwidth 1 1 1\n")
+                     + "contact late\n";
+    assert_eq!(parse(&text).unwrap().validate(), Err(vec![
+        Violation::Repeated("bandwidth", 2),
+        Violation::Missing("published"),
+        Violation::Repeated("contact", 2),
+        Violation::Misplaced("router-signature"),
+    ]));
+}
+
+#[test]
+fn validate_at_most_once_items() {
+    let text = SAMPLE.replace("platform Tor 0.2.6.1-alpha on Linux\n",
+                              "platform Tor 0.2.6.1-alpha on Linux\nplatform Tor 0.2.6.1-alpha on Linux\n")
+                     .replace("uptime 339587\n", "uptime 339587\nuptime 339588\nuptime 339589\n");
+    assert_eq!(parse(&text).unwrap().validate(), Err(vec![
+        Violation::Repeated("platform", 2),
+        Violation::Repeated("uptime", 3),
+    ]));
+}
+
+#[test]
+fn validate_ed25519_items() {
+    // identity-ed25519 moved after the platform line, and router-sig-ed25519 removed
+    let cert_start = ED25519_SAMPLE.find("identity-ed25519\n").unwrap();
+    let cert_end = ED25519_SAMPLE.find("master-key-ed25519").unwrap();
+    let cert = &ED25519_SAMPLE[cert_start..cert_end];
+    let sig_start = ED25519_SAMPLE.find("router-sig-ed25519").unwrap();
+    let sig_end = ED25519_SAMPLE.find("router-signature\n").unwrap();
+    let text = ED25519_SAMPLE.replace(&ED25519_SAMPLE[sig_start..sig_end], "")
+                             .replace(cert, "")
+                             .replace("platform Tor 0.2.9.10 on Linux\n",
+                                      &format!("platform Tor 0.2.9.10 on Linux\n{}", cert));
+    assert_eq!(parse(&text).unwrap().validate(), Err(vec![
+        Violation::Misplaced("identity-ed25519"),
+        Violation::Missing("router-sig-ed25519"),
+    ]));
+
+    // onion-key-crosscert removed, and ntor-onion-key-crosscert doubled
+    let onion_start = ED25519_SAMPLE.find("onion-key-crosscert\n").unwrap();
+    let ntor_start = ED25519_SAMPLE.find("ntor-onion-key-crosscert").unwrap();
+    let ntor_end = ED25519_SAMPLE.find("hidden-service-dir").unwrap();
+    let ntor = &ED25519_SAMPLE[ntor_start..ntor_end];
+    let text = ED25519_SAMPLE.replace(&ED25519_SAMPLE[onion_start..ntor_start], "")
+                             .replace(ntor, &ntor.repeat(2));
+    assert_eq!(parse(&text).unwrap().validate(), Err(vec![
+        Violation::Missing("onion-key-crosscert"),
+        Violation::Repeated("ntor-onion-key-crosscert", 2),
+    ]));
+
+    // router-sig-ed25519 without an identity-ed25519
+    let text = SAMPLE.replace("router-signature\n", "router-sig-ed25519 AAAA\nrouter-signature\n");
+    assert_eq!(parse(&text).unwrap().validate(), Err(vec![Violation::Unexpected("router-sig-ed25519")]));
+}
+
 #[test]
 fn parse_all_in_file() {
     let path = Path::new("sample/2016-08-06-03-06-03-server-descriptors");
     // Open the path in read-only mode, returns `io::Result<File>`
-    let file = match File::open(&path) {
+    let file = match File::open(path) {
         Err(why) => panic!("couldn't open {:?}: {}", path, why),
         Ok(file) => file,
     };
@@ -236,3 +513,16 @@ fn parse_all_in_file() {
         println!("{:#?}", d);
     }
 }
+
+#[test]
+fn parse_bridge_server_descriptor() {
+    let sd = parse(BRIDGE_SAMPLE).unwrap();
+    assert_eq!(sd.nickname, "Unnamed");
+    assert_eq!(sd.address,  Some(Ipv4Addr::new(10,152,219,144)));
+    assert_eq!(sd.bridge_distribution_request, Some("any"));
+    assert_eq!(sd.router_digest,        Some("4C61A7EB8AD47B3B0CF88C12FB3F9D9E0BDD3A0F"));
+    assert_eq!(sd.router_digest_sha256, Some("hPvQ0wqfV9pWb/y6YYAFv6NNpsZqW6DfeJH4Y5PG27A"));
+    assert_eq!(sd.router_signature, None);
+    assert!(sd.unprocessed_items.is_empty());
+}
+
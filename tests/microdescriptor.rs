@@ -0,0 +1,87 @@
+extern crate tordesc;
+
+use tordesc::document::Annotation;
+use tordesc::microdescriptor::*;
+use tordesc::network_status;
+use tordesc::server_descriptor::exit_policy::*;
+
+static SAMPLE: &str = include_str!("data/microdescriptor");
+
+static MICRODESC_CONSENSUS: &str = r#"@type network-status-microdesc-consensus-3 1.0
+network-status-version 3 microdesc
+vote-status consensus
+consensus-method 25
+valid-after 2016-08-06 03:00:00
+fresh-until 2016-08-06 04:00:00
+valid-until 2016-08-06 06:00:00
+voting-delay 300 300
+known-flags Authority BadExit Exit Fast Guard HSDir Running Stable V2Dir Valid
+r LetFreedomRing 2kzsk8jS8YfAJ6ltOSXBUx2QqJ4 2016-08-05 21:10:27 24.233.74.111 9001 0
+m IR/ViEHxVCg6oWlstqvVwkV8WLC0nWvs7zZ6kIeSsXU
+s Exit Fast Running Stable Valid
+v Tor 0.2.6.1-alpha
+w Bandwidth=16
+p accept 22,465,993-995,6660-6697
+directory-footer
+"#;
+
+#[test]
+fn parse_microdescriptor() {
+    let md = parse(SAMPLE).unwrap();
+    assert!(md.onion_key.unwrap().starts_with("-----BEGIN RSA PUBLIC KEY-----"));
+    assert_eq!(md.ntor_onion_key,   Some("q8Qg9PaoBm59j7cEJcOrzTUazVt3D8Ax4L3oaO8PaxU="));
+    assert_eq!(md.or_addresses,     vec!["[2001:41d0:1:8989::1]:9001"]);
+    assert_eq!(md.family,           vec!["$0011BD2485AD45D984EC4159C88FC066E5E3300E", "LetFreedomRing"]);
+    assert_eq!(md.identity_rsa1024, Some("2kzsk8jS8YfAJ6ltOSXBUx2QqJ4"));
+    assert_eq!(md.identity_ed25519, Some("Fw9k0pDFoDgXD+Lyg1JgP8HDh/DOoBKAY1AK+dXKvGo"));
+    assert!(md.unprocessed_items.is_empty());
+}
+
+#[test]
+fn parse_policy_summaries() {
+    let md = parse(SAMPLE).unwrap();
+    assert_eq!(md.exit_policy.unwrap().ports.len(), 4);
+    assert_eq!(md.exit_policy_v6, Some(PolicySummary {
+        rule: Rule::Accept,
+        ports: vec![PortSpec::Port(22)],
+    }));
+}
+
+#[test]
+fn microdescriptor_digest() {
+    assert_eq!(parse(SAMPLE).unwrap().digest().base64(), "IR/ViEHxVCg6oWlstqvVwkV8WLC0nWvs7zZ6kIeSsXU");
+}
+
+#[test]
+fn parse_cached_microdescs() {
+    // as in Tor's cached-microdescs file, with no @type but a @last-listed annotation each
+    let cached = SAMPLE.replacen("@type microdescriptor 1.0\n", "@last-listed 2016-08-06 03:00:00\n", 1);
+    let doubled = format!("{}{}", cached, cached);
+    let mds = parse_all(&doubled);
+    assert_eq!(mds.len(), 2);
+    assert_eq!(mds[1].type_annotation, None);
+    assert_eq!(mds[1].annotations, vec![
+        Annotation { key: "last-listed", args: Some("2016-08-06 03:00:00") },
+    ]);
+    // the annotations are not part of the digest
+    assert_eq!(mds[1].digest().base64(), "IR/ViEHxVCg6oWlstqvVwkV8WLC0nWvs7zZ6kIeSsXU");
+}
+
+#[test]
+fn parse_microdesc_consensus() {
+    let c = network_status::parse_microdesc(MICRODESC_CONSENSUS).unwrap();
+    assert_eq!(c.header.network_status_version, Some("3 microdesc"));
+    assert_eq!(c.routers.len(), 1);
+    assert_eq!(c.routers[0].nickname, "LetFreedomRing");
+    assert_eq!(c.routers[0].digest, None);
+    assert_eq!(c.routers[0].microdesc_digest().unwrap().base64(), "IR/ViEHxVCg6oWlstqvVwkV8WLC0nWvs7zZ6kIeSsXU");
+}
+
+#[test]
+fn join_microdesc_consensus_entry() {
+    let c = network_status::parse_microdesc(MICRODESC_CONSENSUS).unwrap();
+    let mds = index_by_digest(parse_all(SAMPLE));
+
+    let md = &mds[&c.routers[0].microdesc_digest().unwrap()];
+    assert_eq!(md.identity_rsa1024, Some(c.routers[0].identity));
+}
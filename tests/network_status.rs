@@ -0,0 +1,237 @@
+extern crate tordesc;
+
+use tordesc::network_status::*;
+use tordesc::server_descriptor::exit_policy::*;
+
+use std::net::Ipv4Addr;
+
+static CONSENSUS: &str = r#"@type network-status-consensus-3 1.0
+network-status-version 3
+vote-status consensus
+consensus-method 25
+valid-after 2016-08-06 03:00:00
+fresh-until 2016-08-06 04:00:00
+valid-until 2016-08-06 06:00:00
+voting-delay 300 300
+client-versions 0.2.4.27,0.2.7.6,0.2.8.6
+server-versions 0.2.4.27,0.2.7.6,0.2.8.6
+known-flags Authority BadExit Exit Fast Guard HSDir Running Stable V2Dir Valid
+params CircuitPriorityHalflifeMsec=30000 NumDirectoryGuards=3 UseOptimisticData=1 bwauthpid=-1
+dir-source dannenberg 0232AF901C31A04EE9848595AF9BB7620D4C5B2E dannenberg.torauth.de 193.23.244.244 80 443
+contact Andreas Lehner
+vote-digest 53D9F2C95C0A7A7D4B6F4B1D8B9C0B6E0A6B6D1E
+dir-source tor26 14C131DFC5C6F93646BE72FA1401C02A8DF2E8B4 86.59.21.38 86.59.21.38 80 443
+contact Peter Palfrader
+vote-digest 6A0F8C02AE1DD5A8BC5D5BE11DAA2E4D1F2C5E9C
+r seele AAoQ1DAR6kkoo19hBAX5K0QztNw oX3I/XP4/WB/bXpc7XJ3UVuXrLU 2016-08-05 21:10:27 67.161.31.147 9001 0
+s Running Stable V2Dir Valid
+v Tor 0.2.7.6
+pr Cons=1-2 Desc=1-2 DirCache=1 HSDir=1 HSIntro=3 HSRend=1 Link=1-4 LinkAuth=1 Microdesc=1-2 Relay=1-2
+w Bandwidth=22
+p reject 1-65535
+r PutoKalimero AAwffNL+oHO5EdyUoWAOwvEX3ws 6H5dczowuTgCdAkD20kbQR4IStg 2016-08-06 01:25:30 91.121.84.137 9001 9030
+a [2001:41d0:1:8989::1]:9001
+s Exit Fast Guard HSDir Running Stable V2Dir Valid
+v Tor 0.2.8.6
+w Bandwidth=8910 Unmeasured=1
+p accept 20-23,43,53,79-81
+directory-footer
+bandwidth-weights Wbd=0 Wbe=0 Wbg=4132 Wbm=10000 Wdb=10000 Web=10000
+directory-signature 0232AF901C31A04EE9848595AF9BB7620D4C5B2E 7D6AFE3F3A9FA5F4E1C0E1E8FF31C74A7DB25A7A
+-----BEGIN SIGNATURE-----
+SGVsbG8gd29ybGQh
+-----END SIGNATURE-----
+directory-signature sha256 14C131DFC5C6F93646BE72FA1401C02A8DF2E8B4 A3D3B1F4D5E4EDD42C4E0A5E8EDB18F57B1A6E12
+-----BEGIN SIGNATURE-----
+SGVsbG8gd29ybGQh
+-----END SIGNATURE-----
+"#;
+
+#[test]
+fn parse_header() {
+    let h = parse(CONSENSUS).unwrap().header;
+    assert_eq!(h.network_status_version, Some("3"));
+    assert_eq!(h.vote_status,            Some("consensus"));
+    assert_eq!(h.consensus_method,       Some(25));
+    assert_eq!(h.valid_after,            Some("2016-08-06 03:00:00"));
+    assert_eq!(h.fresh_until,            Some("2016-08-06 04:00:00"));
+    assert_eq!(h.valid_until,            Some("2016-08-06 06:00:00"));
+    assert_eq!(h.voting_delay,           Some((300, 300)));
+    assert_eq!(h.known_flags.len(),      10);
+    assert_eq!(h.known_flags[0],         "Authority");
+    assert!(h.unprocessed_items.is_empty());
+}
+
+#[test]
+fn parse_params() {
+    let h = parse(CONSENSUS).unwrap().header;
+    assert_eq!(h.params, vec![
+        ("CircuitPriorityHalflifeMsec", 30000),
+        ("NumDirectoryGuards", 3),
+        ("UseOptimisticData", 1),
+        ("bwauthpid", -1),
+    ]);
+}
+
+#[test]
+fn parse_authorities() {
+    let authorities = parse(CONSENSUS).unwrap().authorities;
+    assert_eq!(authorities.len(), 2);
+    assert_eq!(authorities[0].nickname, "dannenberg");
+    assert_eq!(authorities[0].hostname, "dannenberg.torauth.de");
+    assert_eq!(authorities[0].address,  Some(Ipv4Addr::new(193,23,244,244)));
+    assert_eq!(authorities[0].dir_port, 80);
+    assert_eq!(authorities[0].or_port,  443);
+    assert_eq!(authorities[1].contact,  Some("Peter Palfrader"));
+}
+
+#[test]
+fn parse_router_status_entries() {
+    let routers = parse(CONSENSUS).unwrap().routers;
+    assert_eq!(routers.len(), 2);
+
+    let rs = &routers[1];
+    assert_eq!(rs.nickname,     "PutoKalimero");
+    assert_eq!(rs.identity,     "AAwffNL+oHO5EdyUoWAOwvEX3ws");
+    assert_eq!(rs.digest,       Some("6H5dczowuTgCdAkD20kbQR4IStg"));
+    assert_eq!(rs.published,    "2016-08-06 01:25:30");
+    assert_eq!(rs.address,      Some(Ipv4Addr::new(91,121,84,137)));
+    assert_eq!(rs.or_port,      9001);
+    assert_eq!(rs.dir_port,     9030);
+    assert_eq!(rs.or_addresses, vec!["[2001:41d0:1:8989::1]:9001"]);
+    assert_eq!(rs.flags.len(),  8);
+    assert_eq!(rs.version,      Some("Tor 0.2.8.6"));
+    assert_eq!(rs.bandwidth,    Some(8910));
+    assert!(rs.unmeasured);
+    assert_eq!(rs.exit_policy.as_ref().unwrap().rule, Rule::Accept);
+    assert!(rs.unprocessed_items.is_empty());
+
+    assert!(routers[0].protocols.is_some());
+    assert!(!routers[0].unmeasured);
+}
+
+#[test]
+fn parse_footer() {
+    let f = parse(CONSENSUS).unwrap().footer;
+    assert_eq!(f.bandwidth_weights.len(), 6);
+    assert_eq!(f.bandwidth_weights[2], ("Wbg", 4132));
+    assert_eq!(f.signatures.len(), 2);
+    assert_eq!(f.signatures[0].algorithm, "sha1");
+    assert_eq!(f.signatures[0].identity,  "0232AF901C31A04EE9848595AF9BB7620D4C5B2E");
+    assert_eq!(f.signatures[1].algorithm, "sha256");
+    assert_eq!(f.signatures[1].signing_key_digest, "A3D3B1F4D5E4EDD42C4E0A5E8EDB18F57B1A6E12");
+    assert!(f.unprocessed_items.is_empty());
+}
+
+static VOTE: &str = r#"@type network-status-vote-3 1.0
+network-status-version 3
+vote-status vote
+consensus-methods 13 14 15 16 17 18 19 20 21 22 23 24 25
+published 2016-08-06 02:50:00
+valid-after 2016-08-06 03:00:00
+fresh-until 2016-08-06 04:00:00
+valid-until 2016-08-06 06:00:00
+voting-delay 300 300
+known-flags Authority BadExit Exit Fast Guard HSDir Running Stable V2Dir Valid
+flag-thresholds stable-uptime=1199091 stable-mtbf=2530418 fast-speed=69000 guard-wfu=98.000% guard-tk=691200 enough-mtbf=1 ignoring-advertised-bws=1
+params CircuitPriorityHalflifeMsec=30000 NumDirectoryGuards=3
+dir-source gabelmoo F2044413DAC2E02E3D6BCF4735A19BCA1DE97281 131.188.40.189 131.188.40.189 80 443
+contact 4096R/261C5FBE77285F88FB0C343266C8C2D7C5AA446D Sebastian Hahn <tor@sebastianhahn.net>
+dir-key-certificate-version 3
+fingerprint F2044413DAC2E02E3D6BCF4735A19BCA1DE97281
+dir-key-published 2016-01-29 18:55:26
+dir-key-expires 2017-01-29 18:55:26
+dir-identity-key
+-----BEGIN RSA PUBLIC KEY-----
+SGVsbG8gd29ybGQh
+-----END RSA PUBLIC KEY-----
+dir-signing-key
+-----BEGIN RSA PUBLIC KEY-----
+SGVsbG8gd29ybGQh
+-----END RSA PUBLIC KEY-----
+dir-key-crosscert
+-----BEGIN ID SIGNATURE-----
+SGVsbG8gd29ybGQh
+-----END ID SIGNATURE-----
+dir-key-certification
+-----BEGIN SIGNATURE-----
+SGVsbG8gd29ybGQh
+-----END SIGNATURE-----
+r seele AAoQ1DAR6kkoo19hBAX5K0QztNw oX3I/XP4/WB/bXpc7XJ3UVuXrLU 2016-08-05 21:10:27 67.161.31.147 9001 0
+s Running Stable V2Dir Valid
+v Tor 0.2.7.6
+w Bandwidth=22 Measured=11
+p reject 1-65535
+id ed25519 none
+m 13,14,15,16,17,18,19,20 sha256=2vbT7sI3pi8TnIbaeWmLd8qmaqFLcbwGcvgtDtFOM5I
+m 21,22,23,24,25 sha256=0Q2mUoCUkGkzsMb8mwgQwhyxlabaa3FVSz+KWlKvMv4
+directory-footer
+directory-signature F2044413DAC2E02E3D6BCF4735A19BCA1DE97281 D6ABED0D4A6E7C8CDF43E49F6845DE51E7F3F0CA
+-----BEGIN SIGNATURE-----
+SGVsbG8gd29ybGQh
+-----END SIGNATURE-----
+"#;
+
+#[test]
+fn parse_vote_header() {
+    let h = vote::parse(VOTE).unwrap().header;
+    assert_eq!(h.vote_status,             Some("vote"));
+    assert_eq!(h.published,               Some("2016-08-06 02:50:00"));
+    assert_eq!(h.consensus_methods.len(), 13);
+    assert_eq!(h.flag_thresholds[3],      ("guard-wfu", "98.000%"));
+    assert_eq!(h.flag_thresholds.len(),   7);
+    assert!(h.unprocessed_items.is_empty());
+}
+
+#[test]
+fn parse_vote_authority() {
+    let v = vote::parse(VOTE).unwrap();
+    assert_eq!(v.authority.nickname, "gabelmoo");
+    assert_eq!(v.authority.identity, "F2044413DAC2E02E3D6BCF4735A19BCA1DE97281");
+    assert!(v.authority.contact.is_some());
+    assert!(v.authority.unprocessed_items.is_empty());
+
+    assert_eq!(v.key_certificate.version, Some(3));
+    assert_eq!(v.key_certificate.fingerprint, Some("F2044413DAC2E02E3D6BCF4735A19BCA1DE97281"));
+    assert_eq!(v.key_certificate.crosscert.unwrap().keyword, "ID SIGNATURE");
+    assert!(v.key_certificate.unprocessed_items.is_empty());
+}
+
+#[test]
+fn parse_vote_router_status_entries() {
+    let v = vote::parse(VOTE).unwrap();
+    assert_eq!(v.routers.len(), 1);
+
+    let rs = &v.routers[0];
+    assert_eq!(rs.bandwidth,  Some(22));
+    assert_eq!(rs.measured,   Some(11));
+    assert_eq!(rs.ed25519_id, Some("none"));
+    assert_eq!(rs.microdesc_digests.len(), 2);
+    assert!(rs.unprocessed_items.is_empty());
+
+    assert_eq!(v.footer.signatures.len(), 1);
+}
+
+#[test]
+fn parse_bridge_network_status() {
+    let ns = parse_bridge(r#"@type bridge-network-status 1.2
+published 2016-08-06 03:07:15
+flag-thresholds stable-uptime=1210917 stable-mtbf=2829385 fast-speed=56000 guard-wfu=98.000% guard-tk=691200 guard-bw-inc-exits=1262000 guard-bw-exc-exits=1262000 enough-mtbf=1 ignoring-advertised-bws=0
+fingerprint 4A0CCD2DDC7995083D73F5D667100C8A5831F16D
+r Unnamed ABk0wg4j6BLCdZKleVtmNrfzJGI XCyVWuoVkHh+b+lwtA9GeYU8/3Y 2016-08-06 02:04:34 10.208.25.213 443 0
+a [fd9f:2e19:3bcf::2b:8eb6]:443
+s Fast Running Stable Valid
+w Bandwidth=96
+p reject 1-65535
+r Unnamed ACoe4iH/4l6Rx1V5a2m8Hq0sEbo 1oUKrvzOeBeW9aQ8kD7zyBTY3Bs 2016-08-06 01:58:51 10.26.162.212 443 0
+s Fast Running Valid
+w Bandwidth=1
+p reject 1-65535
+"#).unwrap();
+    assert_eq!(ns.header.published, Some("2016-08-06 03:07:15"));
+    assert_eq!(ns.header.flag_thresholds.len(), 9);
+    assert!(ns.authorities.is_empty());
+    assert_eq!(ns.routers.len(), 2);
+    assert_eq!(ns.routers[0].or_addresses, vec!["[fd9f:2e19:3bcf::2b:8eb6]:443"]);
+    assert_eq!(ns.routers[1].bandwidth, Some(1));
+}
@@ -0,0 +1,53 @@
+extern crate tordesc;
+
+use std::io::{BufReader, Cursor, ErrorKind};
+use tordesc::{microdescriptor, DescriptorReader};
+
+static SAMPLE: &str = include_str!("data/microdescriptor");
+
+#[test]
+fn read_annotated() {
+    let input = format!("{}{}{}", SAMPLE, SAMPLE, SAMPLE);
+    // a tiny buffer, so that every descriptor straddles several reads
+    let reader = DescriptorReader::new(BufReader::with_capacity(7, Cursor::new(input)), "onion-key");
+    let texts: Vec<String> = reader.map(Result::unwrap).collect();
+    assert_eq!(texts, vec![SAMPLE; 3]);
+}
+
+#[test]
+fn read_unannotated() {
+    let bare = SAMPLE.replacen("@type microdescriptor 1.0\n", "", 1);
+    let cached = SAMPLE.replacen("@type microdescriptor 1.0\n", "@last-listed 2016-08-06 03:00:00\n", 1);
+    let input = format!("{}{}{}", bare, cached, bare);
+    let texts: Vec<String> = DescriptorReader::new(Cursor::new(input), "onion-key")
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(texts, vec![bare.clone(), cached, bare]);
+
+    for text in &texts {
+        let md = microdescriptor::parse(text).unwrap();
+        assert_eq!(md.family, vec!["$0011BD2485AD45D984EC4159C88FC066E5E3300E", "LetFreedomRing"]);
+    }
+}
+
+#[test]
+fn read_empty() {
+    assert_eq!(DescriptorReader::new(Cursor::new(""), "router").count(), 0);
+}
+
+#[test]
+fn read_invalid_utf8() {
+    // a Latin-1 "é" in the middle descriptor's family line
+    let (before, after) = SAMPLE.split_at(SAMPLE.find("LetFreedomRing").unwrap());
+    let mut input = SAMPLE.as_bytes().to_vec();
+    input.extend_from_slice(before.as_bytes());
+    input.push(0xe9);
+    input.extend_from_slice(after.as_bytes());
+    input.extend_from_slice(SAMPLE.as_bytes());
+
+    let results: Vec<_> = DescriptorReader::new(Cursor::new(input), "onion-key").collect();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap(), SAMPLE);
+    assert_eq!(results[1].as_ref().unwrap_err().kind(), ErrorKind::InvalidData);
+    assert_eq!(results[2].as_ref().unwrap(), SAMPLE);
+}
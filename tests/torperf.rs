@@ -0,0 +1,90 @@
+extern crate tordesc;
+
+use std::time::{Duration, UNIX_EPOCH};
+use tordesc::ErrorKind;
+use tordesc::digest::Digest;
+use tordesc::torperf::*;
+
+static SAMPLE: &str = r#"@type torperf 1.0
+BUILDTIMES=0.440000009537,0.640000104904,0.940000057220 CIRC_ID=4658 CONNECT=1470456001.55 DATACOMPLETE=1470456003.27 DATAPERC10=1470456002.29 DATAPERC20=1470456002.43 DATAPERC30=1470456002.57 DATAPERC40=1470456002.71 DATAPERC50=1470456002.85 DATAPERC60=1470456002.99 DATAPERC70=1470456003.13 DATAPERC80=1470456003.13 DATAPERC90=1470456003.27 DATAREQUEST=1470456001.99 DATARESPONSE=1470456002.15 DIDTIMEOUT=0 FILESIZE=51200 LAUNCH=1470455982.53 NEGOTIATE=1470456001.55 PATH=$F8C4F2A5F06D1EA3E9A37CA5B9B6C2C1D9D36E59,$6A7551EEE18F78A9813096E82BF84F740D32B911,$4A0C3E177AF684581EF780981AD6E8F2CCB36B8D QUANTILE=0.800000 READBYTES=51269 REQUEST=1470456001.55 RESPONSE=1470456001.99 SOCKET=1470456001.55 SOURCE=moria START=1470456001.55 TIMEOUT=1500 USED_AT=1470456003.28 USED_BY=4832 WRITEBYTES=82
+BUILDTIMES=0.309999942780,0.450000047684,0.569999933243 CONNECT=1470456301.56 DATACOMPLETE=0.0 DIDTIMEOUT=1 FILESIZE=51200 SOURCE=moria START=1470456301.56
+"#;
+
+#[test]
+fn parse_torperf() {
+    let results = parse(SAMPLE).unwrap();
+    assert_eq!(results.len(), 2);
+
+    let tr = &results[0];
+    assert_eq!(tr.source,     Some("moria"));
+    assert_eq!(tr.filesize,   Some(51200));
+    assert_eq!(tr.readbytes,  Some(51269));
+    assert_eq!(tr.writebytes, Some(82));
+    assert_eq!(tr.didtimeout, Some(false));
+    assert_eq!(results[1].didtimeout, Some(true));
+}
+
+#[test]
+fn parse_timestamps() {
+    let tr = &parse(SAMPLE).unwrap()[0];
+    assert_eq!(tr.start, Some(UNIX_EPOCH + Duration::new(1470456001, 550_000_000)));
+    assert_eq!(tr.datacomplete.unwrap().duration_since(tr.start.unwrap()).unwrap(),
+               Duration::new(1, 720_000_000));
+    assert_eq!(tr.dataperc.len(), 9);
+    assert_eq!(tr.dataperc[0], (10, UNIX_EPOCH + Duration::new(1470456002, 290_000_000)));
+    assert_eq!(tr.buildtimes[2], Duration::new(0, 940_000_057));
+}
+
+#[test]
+fn parse_path() {
+    let tr = &parse(SAMPLE).unwrap()[0];
+    let path: Vec<_> = tr.path.iter().map(Digest::hex).collect();
+    assert_eq!(path, vec![
+        "F8C4F2A5F06D1EA3E9A37CA5B9B6C2C1D9D36E59",
+        "6A7551EEE18F78A9813096E82BF84F740D32B911",
+        "4A0C3E177AF684581EF780981AD6E8F2CCB36B8D",
+    ]);
+
+    let input = SAMPLE.replacen("$6A7551EEE18F78A9813096E82BF84F740D32B911", "$6A7551EEE18F78A9", 1);
+    let tr = &parse(&input).unwrap()[0];
+    assert!(tr.path.is_empty());
+    assert!(tr.unprocessed_keys.iter().any(|&(key, _)| key == "PATH"));
+}
+
+#[test]
+fn parse_unprocessed_keys() {
+    let tr = &parse(SAMPLE).unwrap()[0];
+    assert_eq!(tr.unprocessed_keys, vec![
+        ("CIRC_ID", "4658"),
+        ("QUANTILE", "0.800000"),
+        ("TIMEOUT", "1500"),
+        ("USED_BY", "4832"),
+    ]);
+}
+
+#[test]
+fn parse_all_torperf() {
+    let doubled = format!("{}{}", SAMPLE, SAMPLE.replacen("torperf 1.0", "torperf 1.1", 1));
+    let results = parse_all(&doubled);
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[1].type_annotation.unwrap().minor, 0);
+    assert_eq!(results[2].type_annotation.unwrap().minor, 1);
+}
+
+#[test]
+fn parse_each_torperf() {
+    let broken = SAMPLE.replacen("SOURCE=moria START", "SOURCE moria START", 1);
+    let input = format!("{}{}{}", SAMPLE, broken, SAMPLE);
+    let results: Vec<_> = parse_each(&input).collect();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[1].as_ref().unwrap_err().kind, ErrorKind::MalformedKeyValue);
+    assert_eq!(results[2].as_ref().unwrap().len(), 2);
+    assert_eq!(parse_all(&input).len(), 4);
+}
+
+#[test]
+fn parse_error_malformed_pair() {
+    let err = parse("@type torperf 1.0\nFILESIZE=51200 SOURCE moria\n").unwrap_err();
+    assert_eq!(err.kind, ErrorKind::MalformedKeyValue);
+    assert_eq!((err.line, err.column), (2, 1));
+}
@@ -0,0 +1,124 @@
+#![cfg(feature = "verify")]
+
+extern crate tordesc;
+
+use tordesc::server_descriptor::*;
+use tordesc::verify::VerifyError;
+
+// the same sample as zoossh, which carries a genuine signature
+static SAMPLE: &str = include_str!("data/server-descriptor");
+
+// a modern relay descriptor, with ed25519 keys and crosscerts, signed with throwaway keys
+static ED25519_SAMPLE: &str = include_str!("data/server-descriptor-ed25519");
+
+#[test]
+fn verify_router_signature() {
+    assert_eq!(parse(SAMPLE).unwrap().verify_router_signature(), Ok(()));
+    assert_eq!(parse(ED25519_SAMPLE).unwrap().verify_router_signature(), Ok(()));
+}
+
+#[test]
+fn verify_tampered_descriptor() {
+    let tampered = SAMPLE.replacen("uptime 339587", "uptime 339588", 1);
+    assert_eq!(parse(&tampered).unwrap().verify_router_signature(),
+               Err(VerifyError::BadSignature("router-signature")));
+}
+
+#[test]
+fn verify_missing_signature() {
+    let unsigned = &SAMPLE[..SAMPLE.find("router-signature\n").unwrap()];
+    assert_eq!(parse(unsigned).unwrap().verify_router_signature(),
+               Err(VerifyError::Missing("router-signature")));
+
+    let keyless = SAMPLE.replacen("signing-key\n", "x-signing-key\n", 1);
+    assert_eq!(parse(&keyless).unwrap().verify_router_signature(),
+               Err(VerifyError::Missing("signing-key")));
+}
+
+#[test]
+fn verify_malformed_key() {
+    let truncated = SAMPLE.replacen("PJifL3B318lRDjAE2wTVyM+437TLaROLNBrQOF2apjgJYH661vPFG5Uw6+8CXv6w\n", "", 1);
+    let err = parse(&truncated).unwrap().verify_router_signature().unwrap_err();
+    assert_eq!(err, VerifyError::Malformed("signing-key"));
+    assert_eq!(err.to_string(), "malformed signing-key");
+}
+
+#[test]
+fn verify_ed25519() {
+    assert_eq!(parse(ED25519_SAMPLE).unwrap().verify_ed25519(), Ok(()));
+    assert_eq!(parse(SAMPLE).unwrap().verify_ed25519(), Err(VerifyError::Missing("identity-ed25519")));
+}
+
+#[test]
+fn verify_ed25519_tampered_descriptor() {
+    let tampered = ED25519_SAMPLE.replacen("uptime 86400", "uptime 86401", 1);
+    assert_eq!(parse(&tampered).unwrap().verify_ed25519(),
+               Err(VerifyError::BadSignature("router-sig-ed25519")));
+}
+
+#[test]
+fn verify_ed25519_master_key() {
+    let other = ED25519_SAMPLE.replacen("master-key-ed25519 6x5PZL/YwJSIYY+l6C/Oyk6zy0iJDIacMrtg3CMt1xU",
+                                        "master-key-ed25519 OXOkwCc/IGHS9+8WdlK6+A5cu8BwCQeZWO1x/abNXUQ", 1);
+    assert_eq!(parse(&other).unwrap().verify_ed25519(),
+               Err(VerifyError::KeyMismatch("master-key-ed25519")));
+}
+
+#[test]
+fn verify_ed25519_tampered_cert() {
+    // the certified signing key is changed, so the certificate's signature no longer matches
+    let tampered = ED25519_SAMPLE.replacen("AQQAB1MAATlzpMAn", "AQQAB1MAATlzpMAo", 1);
+    assert_eq!(parse(&tampered).unwrap().verify_ed25519(),
+               Err(VerifyError::BadSignature("identity-ed25519")));
+}
+
+#[test]
+fn verify_fingerprint() {
+    assert_eq!(parse(SAMPLE).unwrap().verify_fingerprint(), Ok(()));
+    assert_eq!(parse(ED25519_SAMPLE).unwrap().verify_fingerprint(), Ok(()));
+}
+
+#[test]
+fn verify_fingerprint_mismatch() {
+    let other = SAMPLE.replacen("fingerprint DA4D EC93 C8D2 F187 C027 A96D 3925 C153 1D90 A89E",
+                                "fingerprint 22C3 87B0 22FB 498E A45E 94C2 39AA EAAE E682 722D", 1);
+    let err = parse(&other).unwrap().verify_fingerprint().unwrap_err();
+    assert_eq!(err, VerifyError::FingerprintMismatch {
+        computed: "DA4D EC93 C8D2 F187 C027 A96D 3925 C153 1D90 A89E".to_string(),
+        claimed:  "22C3 87B0 22FB 498E A45E 94C2 39AA EAAE E682 722D".to_string(),
+    });
+    assert_eq!(err.to_string(), "fingerprint 22C3 87B0 22FB 498E A45E 94C2 39AA EAAE E682 722D does not \
+                                 match the identity key, whose fingerprint is \
+                                 DA4D EC93 C8D2 F187 C027 A96D 3925 C153 1D90 A89E");
+}
+
+#[test]
+fn verify_crosscerts() {
+    assert_eq!(parse(ED25519_SAMPLE).unwrap().verify_crosscerts(), Ok(()));
+    assert_eq!(parse(SAMPLE).unwrap().verify_crosscerts(), Err(VerifyError::Missing("identity-ed25519")));
+}
+
+#[test]
+fn verify_crosscerts_wrong_sign() {
+    let flipped = ED25519_SAMPLE.replacen("ntor-onion-key-crosscert 1\n", "ntor-onion-key-crosscert 0\n", 1);
+    assert_eq!(parse(&flipped).unwrap().verify_crosscerts(),
+               Err(VerifyError::BadSignature("ntor-onion-key-crosscert")));
+}
+
+#[test]
+fn verify_crosscerts_malformed_sign() {
+    // the item is there, just not usable
+    let bad = ED25519_SAMPLE.replacen("ntor-onion-key-crosscert 1\n", "ntor-onion-key-crosscert 2\n", 1);
+    assert_eq!(parse(&bad).unwrap().verify_crosscerts(),
+               Err(VerifyError::Malformed("ntor-onion-key-crosscert")));
+}
+
+#[test]
+fn verify_crosscerts_other_onion_key() {
+    // the onion key of another relay can't have signed this relay's identity
+    let sd = parse(ED25519_SAMPLE).unwrap();
+    let other = parse(SAMPLE).unwrap();
+    let swapped = ED25519_SAMPLE.replacen(sd.onion_key.unwrap(), other.onion_key.unwrap(), 1);
+    assert_eq!(parse(&swapped).unwrap().verify_crosscerts(),
+               Err(VerifyError::BadSignature("onion-key-crosscert")));
+}